@@ -0,0 +1,336 @@
+//! Rules over the processor of the Mac.
+
+use std::{collections::BTreeMap, fmt::Display, str::FromStr};
+
+use crate::{
+    Chip, ChipTier, CpuPolicy, Error, NativeSystem, Rule, SystemInfo, HW_OPTIONAL_ARM64,
+    MACHDEP_CPU_BRAND_STRING,
+};
+
+/// Instruction set architecture of a processor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "String", into = "String")
+)]
+pub enum Architecture {
+    /// Intel, `x86_64`.
+    X86,
+    /// Apple silicon, `arm64`.
+    Arm64,
+}
+
+impl Architecture {
+    /// Reads the architecture from [`HW_OPTIONAL_ARM64`], falling back to the brand of the
+    /// processor on Intel Macs, which lack the key.
+    ///
+    /// # Errors
+    ///
+    /// Errors if neither value can be read or understood.
+    pub fn read(system: &dyn SystemInfo) -> Result<Self, Error> {
+        match system.value_string(HW_OPTIONAL_ARM64).as_deref() {
+            Ok("1") => return Ok(Architecture::Arm64),
+            Ok("0") => return Ok(Architecture::X86),
+            _ => {}
+        }
+        let brand = system.value_string(MACHDEP_CPU_BRAND_STRING)?;
+        match Chip::from_brand(&brand) {
+            Some(Chip::Intel) => Ok(Architecture::X86),
+            Some(Chip::AppleSilicon { .. }) => Ok(Architecture::Arm64),
+            None => Err(unknown_brand(brand)),
+        }
+    }
+}
+
+impl Display for Architecture {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Architecture::X86 => "x86_64",
+            Architecture::Arm64 => "arm64",
+        })
+    }
+}
+
+impl FromStr for Architecture {
+    type Err = ParseCpuError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "x86_64" => Ok(Architecture::X86),
+            "arm64" => Ok(Architecture::Arm64),
+            _ => Err(ParseCpuError {
+                input: s.to_owned(),
+            }),
+        }
+    }
+}
+
+impl TryFrom<String> for Architecture {
+    type Error = ParseCpuError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Architecture> for String {
+    fn from(value: Architecture) -> Self {
+        value.to_string()
+    }
+}
+
+/// A generation of Apple silicon chips, optionally narrowed to a tier, e.g. `M1` or `M2 Pro`.
+///
+/// # Example
+///
+/// ```
+/// use dikc_detector::{Chip, ChipGeneration, ChipTier};
+///
+/// let m2: ChipGeneration = "M2".parse().unwrap();
+/// assert!(m2.matches(&Chip::from_brand("Apple M2 Max").unwrap()));
+/// let m2_pro: ChipGeneration = "M2 Pro".parse().unwrap();
+/// assert_eq!(m2_pro.tier, Some(ChipTier::Pro));
+/// assert!(!m2_pro.matches(&Chip::Intel));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "String", into = "String")
+)]
+#[non_exhaustive]
+pub struct ChipGeneration {
+    /// Generation, e.g. `2` for M2.
+    pub generation: u8,
+    /// Tier within the generation, any if unset.
+    pub tier: Option<ChipTier>,
+}
+
+impl ChipGeneration {
+    /// Whether `chip` belongs to this generation and tier.
+    pub fn matches(&self, chip: &Chip) -> bool {
+        match chip {
+            Chip::Intel => false,
+            Chip::AppleSilicon { generation, tier } => {
+                *generation == self.generation && self.tier.map_or(true, |t| t == *tier)
+            }
+        }
+    }
+}
+
+impl Display for ChipGeneration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.tier {
+            Some(tier) => Chip::AppleSilicon {
+                generation: self.generation,
+                tier,
+            }
+            .fmt(f),
+            None => write!(f, "M{}", self.generation),
+        }
+    }
+}
+
+impl FromStr for ChipGeneration {
+    type Err = ParseCpuError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseCpuError {
+            input: s.to_owned(),
+        };
+        match Chip::from_brand(&format!("Apple {}", s.trim())).ok_or_else(err)? {
+            Chip::AppleSilicon { generation, tier } => Ok(ChipGeneration {
+                generation,
+                tier: (s.trim().contains(' ')).then_some(tier),
+            }),
+            Chip::Intel => Err(err()),
+        }
+    }
+}
+
+impl TryFrom<String> for ChipGeneration {
+    type Error = ParseCpuError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<ChipGeneration> for String {
+    fn from(value: ChipGeneration) -> Self {
+        value.to_string()
+    }
+}
+
+/// Error when parsing an [`Architecture`] or a [`ChipGeneration`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseCpuError {
+    input: String,
+}
+
+impl Display for ParseCpuError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "invalid processor `{}`, expected x86_64, arm64 or a chip like M2 or M2 Pro",
+            self.input
+        )
+    }
+}
+
+impl std::error::Error for ParseCpuError {}
+
+fn unknown_brand(brand: String) -> Error {
+    Error::UnexpectedValue {
+        key: MACHDEP_CPU_BRAND_STRING.to_owned(),
+        value: brand,
+        expected: "an Intel or Apple silicon processor".to_owned(),
+    }
+}
+
+/// Checks whether the architecture and chip generation of the processor are allowed by a
+/// [`CpuPolicy`], passing without reading anything if the policy is empty.
+///
+/// Values are read through [`NativeSystem`], so processes translated by Rosetta see the real chip.
+#[derive(Debug, Clone, Default)]
+pub struct CpuRule {
+    policy: CpuPolicy,
+}
+
+impl CpuRule {
+    /// Identifier of this rule.
+    pub const ID: &'static str = "bad-cpu";
+
+    /// Creates the rule enforcing `policy`.
+    pub fn new(policy: CpuPolicy) -> Self {
+        Self { policy }
+    }
+}
+
+impl Rule for CpuRule {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+
+    fn description(&self) -> &str {
+        "Processor is allowed by the policy"
+    }
+
+    fn evaluate(&self, system: &dyn SystemInfo) -> Result<(), Error> {
+        let policy = &self.policy;
+        if policy.is_empty() {
+            return Ok(());
+        }
+        let system = &NativeSystem::new(system);
+        let architecture = Architecture::read(system)?;
+        let chip = if policy.require_chip.is_empty() && policy.forbid_chip.is_empty() {
+            None
+        } else {
+            let brand = system.value_string(MACHDEP_CPU_BRAND_STRING)?;
+            Some(Chip::from_brand(&brand).ok_or_else(|| unknown_brand(brand))?)
+        };
+        if policy.allows(architecture, chip.as_ref()) {
+            Ok(())
+        } else {
+            Err(Error::BadCpu { architecture, chip })
+        }
+    }
+
+    fn details(&self, system: &dyn SystemInfo) -> BTreeMap<String, String> {
+        let system = &NativeSystem::new(system);
+        let mut details = BTreeMap::new();
+        if let Ok(architecture) = Architecture::read(system) {
+            details.insert("cpu.architecture".to_owned(), architecture.to_string());
+        }
+        let chip = system
+            .value_string(MACHDEP_CPU_BRAND_STRING)
+            .ok()
+            .and_then(|brand| Chip::from_brand(&brand));
+        if let Some(chip) = chip {
+            details.insert("cpu.chip".to_owned(), chip.to_string());
+        }
+        details
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Architecture, ChipGeneration, CpuRule};
+    use crate::{
+        Chip, ChipTier, CpuPolicy, Error, FakeSystem, Rule, HW_OPTIONAL_ARM64,
+        MACHDEP_CPU_BRAND_STRING,
+    };
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            Chip::from_brand("Apple M2 Pro"),
+            Some(Chip::AppleSilicon {
+                generation: 2,
+                tier: ChipTier::Pro
+            })
+        );
+        assert_eq!(
+            Chip::from_brand("Intel(R) Core(TM) i9-9880H CPU @ 2.30GHz"),
+            Some(Chip::Intel)
+        );
+        assert_eq!(Chip::from_brand("VirtualApple @ 2.50GHz processor"), None);
+        assert_eq!("x86_64".parse(), Ok(Architecture::X86));
+        assert!("M2 Turbo".parse::<ChipGeneration>().is_err());
+        assert!("Intel".parse::<ChipGeneration>().is_err());
+        assert_eq!("M3".parse::<ChipGeneration>().unwrap().tier, None);
+        assert_eq!(
+            "M3 Max".parse::<ChipGeneration>().unwrap().to_string(),
+            "M3 Max"
+        );
+    }
+
+    #[test]
+    fn test_cpu() {
+        let m1 = FakeSystem::new()
+            .with_value(HW_OPTIONAL_ARM64, "1")
+            .with_value(MACHDEP_CPU_BRAND_STRING, "Apple M1");
+        let intel = FakeSystem::new().with_value(
+            MACHDEP_CPU_BRAND_STRING,
+            "Intel(R) Core(TM) i7-8569U CPU @ 2.80GHz",
+        );
+        assert_eq!(Architecture::read(&intel).unwrap(), Architecture::X86);
+
+        let policy = CpuPolicy {
+            require_arch: vec![Architecture::Arm64],
+            forbid_chip: vec!["M1".parse().unwrap()],
+            ..CpuPolicy::default()
+        };
+        let rule = CpuRule::new(policy);
+        assert!(matches!(
+            rule.evaluate(&intel),
+            Err(Error::BadCpu {
+                architecture: Architecture::X86,
+                chip: Some(Chip::Intel),
+            })
+        ));
+        assert!(rule.evaluate(&m1).is_err());
+        assert!(rule
+            .evaluate(
+                &m1.clone()
+                    .with_value(MACHDEP_CPU_BRAND_STRING, "Apple M2 Pro")
+            )
+            .is_ok());
+        assert_eq!(rule.details(&m1)["cpu.chip"], "M1");
+
+        let require = CpuRule::new(CpuPolicy {
+            require_chip: vec!["M2 Pro".parse().unwrap(), "M3".parse().unwrap()],
+            ..CpuPolicy::default()
+        });
+        assert!(require.evaluate(&m1).is_err());
+        assert!(require
+            .evaluate(
+                &m1.clone()
+                    .with_value(MACHDEP_CPU_BRAND_STRING, "Apple M3 Ultra")
+            )
+            .is_ok());
+
+        assert!(CpuRule::default().evaluate(&FakeSystem::new()).is_ok());
+    }
+}
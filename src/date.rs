@@ -0,0 +1,182 @@
+//! Calendar dates.
+
+use std::{
+    fmt::Display,
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// A calendar date, written as `YYYY-MM-DD`.
+///
+/// # Example
+///
+/// ```
+/// use dikc_detector::Date;
+///
+/// let date: Date = "2024-09-16".parse().unwrap();
+/// assert_eq!(date, Date::new(2024, 9, 16));
+/// assert!(date < Date::new(2025, 1, 1));
+/// assert_eq!(date.to_string(), "2024-09-16");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "String", into = "String")
+)]
+pub struct Date {
+    year: u16,
+    month: u8,
+    day: u8,
+}
+
+impl Date {
+    /// Creates a date, which is expected to be valid.
+    pub const fn new(year: u16, month: u8, day: u8) -> Self {
+        Self { year, month, day }
+    }
+
+    /// The current date in UTC.
+    pub fn today() -> Self {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        Self::from_unix_days(secs / 86_400)
+    }
+
+    /// The date `days` days after 1970-01-01.
+    fn from_unix_days(days: u64) -> Self {
+        // Howard Hinnant's `civil_from_days`, with eras of 400 years starting on March 1st.
+        let days = days + 719_468;
+        let era = days / 146_097;
+        let day_of_era = days % 146_097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month + 2) / 5 + 1;
+        let month = if month < 10 { month + 3 } else { month - 9 };
+        let year = year_of_era + era * 400 + u64::from(month <= 2);
+        Self::new(year as u16, month as u8, day as u8)
+    }
+
+    /// The year.
+    pub const fn year(&self) -> u16 {
+        self.year
+    }
+
+    /// The month, from 1 to 12.
+    pub const fn month(&self) -> u8 {
+        self.month
+    }
+
+    /// The day of the month, from 1.
+    pub const fn day(&self) -> u8 {
+        self.day
+    }
+}
+
+impl Display for Date {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+impl FromStr for Date {
+    type Err = ParseDateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseDateError {
+            input: s.to_owned(),
+        };
+        let mut parts = s.trim().split('-');
+        let mut next = |len: usize| {
+            let part = parts
+                .next()
+                .filter(|part| part.len() == len && part.bytes().all(|b| b.is_ascii_digit()));
+            part.and_then(|part| part.parse::<u16>().ok())
+                .ok_or_else(err)
+        };
+        let (year, month, day) = (next(4)?, next(2)?, next(2)?);
+        if parts.next().is_some() {
+            return Err(err());
+        }
+        let days_in_month = match month {
+            1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+            4 | 6 | 9 | 11 => 30,
+            2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+            2 => 28,
+            _ => return Err(err()),
+        };
+        if !(1..=days_in_month).contains(&day) {
+            return Err(err());
+        }
+        Ok(Self::new(year, month as u8, day as u8))
+    }
+}
+
+impl TryFrom<String> for Date {
+    type Error = ParseDateError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Date> for String {
+    fn from(value: Date) -> Self {
+        value.to_string()
+    }
+}
+
+/// Error when parsing a [`Date`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDateError {
+    input: String,
+}
+
+impl ParseDateError {
+    /// The string that failed to parse.
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+}
+
+impl Display for ParseDateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid date `{}`, expected YYYY-MM-DD", self.input)
+    }
+}
+
+impl std::error::Error for ParseDateError {}
+
+#[cfg(test)]
+mod test {
+    use super::Date;
+
+    #[test]
+    fn test_unix_days() {
+        assert_eq!(Date::from_unix_days(0), Date::new(1970, 1, 1));
+        assert_eq!(Date::from_unix_days(11_016), Date::new(2000, 2, 29));
+        assert_eq!(Date::from_unix_days(19_796), Date::new(2024, 3, 14));
+        assert_eq!(Date::from_unix_days(20_088), Date::new(2024, 12, 31));
+        assert!(Date::today() > Date::new(2024, 1, 1));
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("2024-02-29".parse(), Ok(Date::new(2024, 2, 29)));
+        for bad in [
+            "",
+            "2023-02-29",
+            "1900-02-29",
+            "2024-13-01",
+            "2024-1-01",
+            "2024-01-00",
+            "2024-01-01-01",
+            "24-01-01",
+        ] {
+            assert_eq!(bad.parse::<Date>().unwrap_err().input(), bad);
+        }
+    }
+}
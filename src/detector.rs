@@ -0,0 +1,265 @@
+//! Registry of rules.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    report::{Finding, Outcome, Recorder, Report},
+    system::KEYS,
+    BetaRule, CpuCoresRule, CpuRule, Date, DiskSpaceRule, EndOfLifeRule, Error, MacModelRule,
+    MemoryRule, Policy, PosixRule, RosettaRule, Rule, Severity, Simulation, SystemInfo,
+    SystemSnapshot, VersionCompatRule, VmExempt, VmPolicy, VmRule, Waiver, WhatIf,
+};
+
+/// Runs a set of [`Rule`]s against [`SystemInfo`].
+///
+/// [`Detector::new`] starts with the built-in rules, [`Detector::with_policy`] with the built-in
+/// rules configured by a [`Policy`], and [`Detector::empty`] without any.
+///
+/// # Example
+///
+/// ```
+/// use dikc_detector::{Detector, FakeSystem, MacModelRule};
+///
+/// let detector = Detector::new().disable(MacModelRule::ID);
+/// let system = FakeSystem::new()
+///     .with_os_product_version("14.3.1")
+///     .with_kernel_release("23.3.0")
+///     .with_hw_model("MacBookPro16,1");
+/// assert!(detector.run(&system).is_ok());
+/// ```
+pub struct Detector {
+    rules: Vec<Box<dyn Rule>>,
+    disabled: HashSet<String>,
+    severities: HashMap<String, Severity>,
+    threshold: Severity,
+    waivers: Vec<Waiver>,
+    date: Option<Date>,
+}
+
+impl Detector {
+    /// Creates a detector with the built-in rules.
+    pub fn new() -> Self {
+        Self::with_policy(&Policy::default())
+    }
+
+    /// Creates a detector with the built-in rules configured by `policy`.
+    pub fn with_policy(policy: &Policy) -> Self {
+        let detector = Self::empty()
+            .rule(PosixRule::new(policy.os.clone()))
+            .rule(MacModelRule::new(policy.models.clone()))
+            .rule(VersionCompatRule)
+            .rule(BetaRule::new(policy.os.forbid_beta))
+            .rule(EndOfLifeRule::new(policy.os.forbid_end_of_life))
+            .rule(RosettaRule)
+            .rule(VmRule::new(&policy.vm))
+            .hardware_rule(CpuRule::new(policy.cpu.clone()), &policy.vm)
+            .hardware_rule(MemoryRule::new(&policy.hardware), &policy.vm)
+            .hardware_rule(CpuCoresRule::new(&policy.hardware), &policy.vm)
+            .hardware_rule(DiskSpaceRule::new(&policy.hardware), &policy.vm)
+            .fail_at(policy.fail_at);
+        let detector = policy.sysctl.iter().cloned().fold(detector, Self::rule);
+        let detector = policy
+            .severity
+            .iter()
+            .fold(detector, |detector, (id, &severity)| {
+                detector.severity(id.clone(), severity)
+            });
+        policy.waivers.iter().cloned().fold(detector, Self::waiver)
+    }
+
+    /// Creates a detector without any rules.
+    pub fn empty() -> Self {
+        Self {
+            rules: Vec::new(),
+            disabled: HashSet::new(),
+            severities: HashMap::new(),
+            threshold: Severity::default(),
+            waivers: Vec::new(),
+            date: None,
+        }
+    }
+
+    /// Registers `rule`.
+    pub fn rule(mut self, rule: impl Rule + 'static) -> Self {
+        self.rules.push(Box::new(rule));
+        self
+    }
+
+    /// Registers `rule`, wrapped in [`VmExempt`] if `vm` exempts virtual machines from hardware
+    /// rules.
+    fn hardware_rule(self, rule: impl Rule + 'static, vm: &VmPolicy) -> Self {
+        if vm.exempt_hardware {
+            self.rule(VmExempt::new(rule))
+        } else {
+            self.rule(rule)
+        }
+    }
+
+    /// Disables every rule whose [`Rule::id`] is `id`.
+    pub fn disable(mut self, id: impl Into<String>) -> Self {
+        self.disabled.insert(id.into());
+        self
+    }
+
+    /// Overrides the [`Rule::severity`] of every rule whose [`Rule::id`] is `id`, e.g. to demote a
+    /// rule to a warning.
+    pub fn severity(mut self, id: impl Into<String>, severity: Severity) -> Self {
+        self.severities.insert(id.into(), severity);
+        self
+    }
+
+    /// Sets the lowest severity at which findings fail [`Detector::run`], [`Severity::Error`] by
+    /// default.
+    pub fn fail_at(mut self, threshold: Severity) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// The lowest severity at which findings fail [`Detector::run`].
+    pub fn threshold(&self) -> Severity {
+        self.threshold
+    }
+
+    /// Registers `waiver`, turning failures of its rule on matching Macs into
+    /// [`Outcome::Waived`] until it expires.
+    pub fn waiver(mut self, waiver: Waiver) -> Self {
+        self.waivers.push(waiver);
+        self
+    }
+
+    /// Evaluates waivers as of `date` instead of today.
+    pub fn date(mut self, date: Date) -> Self {
+        self.date = Some(date);
+        self
+    }
+
+    /// The severity of `rule`, with the overrides of [`Detector::severity`].
+    pub fn severity_of(&self, rule: &dyn Rule) -> Severity {
+        self.severities
+            .get(rule.id())
+            .copied()
+            .unwrap_or_else(|| rule.severity())
+    }
+
+    /// Iterates over the enabled rules in registration order.
+    pub fn rules(&self) -> impl Iterator<Item = &dyn Rule> {
+        self.rules
+            .iter()
+            .map(|rule| rule.as_ref())
+            .filter(|rule| !self.disabled.contains(rule.id()))
+    }
+
+    /// Runs every enabled rule against `system` and reports the outcome of every registered rule.
+    ///
+    /// Failures covered by an active waiver are reported as [`Outcome::Waived`], while failures
+    /// covered only by expired waivers keep failing with a `waiver.expired` detail. Once a rule
+    /// errors with [`Error::UnsupportedPlatform`], the remaining rules are skipped.
+    pub fn report(&self, system: &dyn SystemInfo) -> Report {
+        let today = self.date.unwrap_or_else(Date::today);
+        let mut unsupported = false;
+        let findings = self
+            .rules
+            .iter()
+            .map(|rule| {
+                let severity = self.severity_of(rule.as_ref());
+                if unsupported || self.disabled.contains(rule.id()) {
+                    return Finding::new(rule.id(), rule.description(), severity, Outcome::Skipped);
+                }
+                let recorder = Recorder::new(system);
+                let outcome = Outcome::from_result(rule.evaluate(&recorder));
+                unsupported = matches!(outcome, Outcome::Error(Error::UnsupportedPlatform));
+                let mut finding = Finding::new(rule.id(), rule.description(), severity, outcome);
+                finding.details = rule.details(&recorder);
+                if matches!(finding.outcome, Outcome::Fail(_)) {
+                    self.apply_waivers(&mut finding, &recorder, today);
+                }
+                finding.observed = recorder.into_observed();
+                finding
+            })
+            .collect();
+        Report {
+            findings,
+            threshold: self.threshold,
+        }
+    }
+
+    /// Turns the failed `finding` into [`Outcome::Waived`] if an active waiver matches `system`.
+    fn apply_waivers(&self, finding: &mut Finding, system: &dyn SystemInfo, today: Date) {
+        let matching = self
+            .waivers
+            .iter()
+            .filter(|waiver| waiver.rule == finding.rule_id && waiver.matches(system));
+        let Some(waiver) = matching.clone().find(|waiver| waiver.is_active(today)) else {
+            if let Some(expired) = matching.map(|waiver| waiver.expires).max() {
+                finding
+                    .details
+                    .insert("waiver.expired".to_owned(), expired.to_string());
+            }
+            return;
+        };
+        if let Outcome::Fail(err) = std::mem::replace(&mut finding.outcome, Outcome::Skipped) {
+            finding.outcome = Outcome::Waived(err);
+        }
+        finding.waiver = Some(waiver.clone());
+    }
+
+    /// Records every value the built-in rules and every registered rule, including disabled
+    /// ones, read from `system`, so that the checks can be replayed later.
+    pub fn capture(&self, system: &dyn SystemInfo) -> SystemSnapshot {
+        let mut snapshot = SystemSnapshot::capture(system, KEYS.iter().copied());
+        for rule in &self.rules {
+            let recorder = Recorder::new(system);
+            let _ = rule.evaluate(&recorder);
+            let _ = rule.details(&recorder);
+            for waiver in self
+                .waivers
+                .iter()
+                .filter(|waiver| waiver.rule == rule.id())
+            {
+                let _ = waiver.matches(&recorder);
+            }
+            snapshot.extend(recorder.into_observed());
+        }
+        snapshot
+    }
+
+    /// Reports on `system` both as is and with the overrides of `what_if`.
+    pub fn simulate(&self, system: &dyn SystemInfo, what_if: &WhatIf) -> Simulation {
+        Simulation {
+            actual: self.report(system),
+            simulated: self.report(&what_if.apply(system)),
+        }
+    }
+
+    /// Runs every enabled rule against `system`.
+    ///
+    /// # Errors
+    ///
+    /// Errors with the error of the failed rule at or above [`Detector::threshold`], or
+    /// [`Error::Many`] if more than one rule failed.
+    pub fn run(&self, system: &dyn SystemInfo) -> Result<(), Error> {
+        self.report(system).into_result_at(self.threshold)
+    }
+}
+
+impl Default for Detector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for Detector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Detector")
+            .field(
+                "rules",
+                &self.rules.iter().map(|rule| rule.id()).collect::<Vec<_>>(),
+            )
+            .field("disabled", &self.disabled)
+            .field("severities", &self.severities)
+            .field("threshold", &self.threshold)
+            .field("waivers", &self.waivers)
+            .field("date", &self.date)
+            .finish()
+    }
+}
@@ -0,0 +1,301 @@
+//! Rules over the hardware resources of the Mac.
+
+use std::{collections::BTreeMap, fmt::Display};
+
+use crate::{
+    Error, HardwarePolicy, Rule, Severity, SystemInfo, HW_LOGICALCPU, HW_MEMSIZE, HW_PHYSICALCPU,
+    ROOT_VOLUME_FREE,
+};
+
+/// Bytes per GiB, the unit of the memory and disk thresholds of a [`HardwarePolicy`].
+const GIB: u64 = 1 << 30;
+
+/// A hardware resource with a minimum in a [`HardwarePolicy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Resource {
+    /// Physical memory in bytes (`hw.memsize`).
+    Memory,
+    /// Physical CPU cores (`hw.physicalcpu`).
+    PhysicalCores,
+    /// Logical CPU cores (`hw.logicalcpu`).
+    LogicalCores,
+    /// Free space on the root volume in bytes.
+    DiskSpace,
+}
+
+impl Resource {
+    /// Formats `amount` of the resource, e.g. `16 GiB of memory` or `8 physical cores`.
+    pub fn format(self, amount: u64) -> String {
+        match self {
+            Resource::Memory => format!("{} of memory", format_bytes(amount)),
+            Resource::DiskSpace => format!("{} of free disk space", format_bytes(amount)),
+            Resource::PhysicalCores => format!("{} physical cores", amount),
+            Resource::LogicalCores => format!("{} logical cores", amount),
+        }
+    }
+}
+
+impl Display for Resource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Resource::Memory => write!(f, "memory"),
+            Resource::PhysicalCores => write!(f, "physical cores"),
+            Resource::LogicalCores => write!(f, "logical cores"),
+            Resource::DiskSpace => write!(f, "free disk space"),
+        }
+    }
+}
+
+/// Formats `bytes` in GiB, with one decimal unless whole, e.g. `16 GiB` or `12.5 GiB`.
+fn format_bytes(bytes: u64) -> String {
+    let gib = bytes as f64 / GIB as f64;
+    if bytes % GIB == 0 {
+        format!("{} GiB", bytes / GIB)
+    } else {
+        format!("{:.1} GiB", gib)
+    }
+}
+
+/// Reads the integer value of `name`.
+fn read_amount(system: &dyn SystemInfo, name: &str) -> Result<u64, Error> {
+    let value = system.value_string(name)?;
+    value.trim().parse().map_err(|_| Error::UnexpectedValue {
+        key: name.to_owned(),
+        value,
+        expected: "an integer".to_owned(),
+    })
+}
+
+/// Fails with [`Error::InsufficientHardware`] if `observed` is below `required`.
+fn require(resource: Resource, observed: u64, required: Option<u64>) -> Result<(), Error> {
+    match required {
+        Some(required) if observed < required => Err(Error::InsufficientHardware {
+            resource,
+            observed,
+            required,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Checks whether the Mac has at least [`HardwarePolicy::min_memory_gib`] of memory
+/// (`hw.memsize`), passing without reading anything if unset.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MemoryRule {
+    min_bytes: Option<u64>,
+}
+
+impl MemoryRule {
+    /// Identifier of this rule.
+    pub const ID: &'static str = "low-memory";
+
+    /// Creates the rule enforcing the memory minimum of `policy`.
+    pub fn new(policy: &HardwarePolicy) -> Self {
+        Self {
+            min_bytes: policy.min_memory_gib.map(|gib| gib.saturating_mul(GIB)),
+        }
+    }
+}
+
+impl Rule for MemoryRule {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+
+    fn description(&self) -> &str {
+        "Mac has enough memory"
+    }
+
+    fn evaluate(&self, system: &dyn SystemInfo) -> Result<(), Error> {
+        if self.min_bytes.is_none() {
+            return Ok(());
+        }
+        let memory = read_amount(system, HW_MEMSIZE)?;
+        require(Resource::Memory, memory, self.min_bytes)
+    }
+
+    fn details(&self, system: &dyn SystemInfo) -> BTreeMap<String, String> {
+        if self.min_bytes.is_none() {
+            return BTreeMap::new();
+        }
+        read_amount(system, HW_MEMSIZE)
+            .map(|memory| ("hardware.memory".to_owned(), format_bytes(memory)))
+            .into_iter()
+            .collect()
+    }
+}
+
+/// Checks whether the Mac has at least [`HardwarePolicy::min_physical_cores`] physical
+/// (`hw.physicalcpu`) and [`HardwarePolicy::min_logical_cores`] logical (`hw.logicalcpu`) CPU
+/// cores, passing without reading anything if both are unset.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CpuCoresRule {
+    min_physical: Option<u64>,
+    min_logical: Option<u64>,
+}
+
+impl CpuCoresRule {
+    /// Identifier of this rule.
+    pub const ID: &'static str = "few-cores";
+
+    /// Creates the rule enforcing the core minimums of `policy`.
+    pub fn new(policy: &HardwarePolicy) -> Self {
+        Self {
+            min_physical: policy.min_physical_cores.map(u64::from),
+            min_logical: policy.min_logical_cores.map(u64::from),
+        }
+    }
+}
+
+impl Rule for CpuCoresRule {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+
+    fn description(&self) -> &str {
+        "Mac has enough CPU cores"
+    }
+
+    fn evaluate(&self, system: &dyn SystemInfo) -> Result<(), Error> {
+        if self.min_physical.is_some() {
+            let physical = read_amount(system, HW_PHYSICALCPU)?;
+            require(Resource::PhysicalCores, physical, self.min_physical)?;
+        }
+        if self.min_logical.is_some() {
+            let logical = read_amount(system, HW_LOGICALCPU)?;
+            require(Resource::LogicalCores, logical, self.min_logical)?;
+        }
+        Ok(())
+    }
+
+    fn details(&self, system: &dyn SystemInfo) -> BTreeMap<String, String> {
+        if self.min_physical.is_none() && self.min_logical.is_none() {
+            return BTreeMap::new();
+        }
+        [
+            ("hardware.physical-cores", HW_PHYSICALCPU),
+            ("hardware.logical-cores", HW_LOGICALCPU),
+        ]
+        .into_iter()
+        .filter_map(|(detail, name)| {
+            let cores = read_amount(system, name).ok()?;
+            Some((detail.to_owned(), cores.to_string()))
+        })
+        .collect()
+    }
+}
+
+/// Checks whether the root volume has at least [`HardwarePolicy::min_free_disk_gib`] of free
+/// space ([`ROOT_VOLUME_FREE`]), passing without reading anything if unset.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DiskSpaceRule {
+    min_bytes: Option<u64>,
+}
+
+impl DiskSpaceRule {
+    /// Identifier of this rule.
+    pub const ID: &'static str = "low-disk-space";
+
+    /// Creates the rule enforcing the free space minimum of `policy`.
+    pub fn new(policy: &HardwarePolicy) -> Self {
+        Self {
+            min_bytes: policy.min_free_disk_gib.map(|gib| gib.saturating_mul(GIB)),
+        }
+    }
+}
+
+impl Rule for DiskSpaceRule {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+
+    fn description(&self) -> &str {
+        "Root volume has enough free space"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn evaluate(&self, system: &dyn SystemInfo) -> Result<(), Error> {
+        if self.min_bytes.is_none() {
+            return Ok(());
+        }
+        let free = read_amount(system, ROOT_VOLUME_FREE)?;
+        require(Resource::DiskSpace, free, self.min_bytes)
+    }
+
+    fn details(&self, system: &dyn SystemInfo) -> BTreeMap<String, String> {
+        if self.min_bytes.is_none() {
+            return BTreeMap::new();
+        }
+        read_amount(system, ROOT_VOLUME_FREE)
+            .map(|free| ("hardware.free-disk".to_owned(), format_bytes(free)))
+            .into_iter()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{format_bytes, CpuCoresRule, DiskSpaceRule, MemoryRule, Resource};
+    use crate::{
+        Error, FakeSystem, HardwarePolicy, Rule, HW_LOGICALCPU, HW_MEMSIZE, HW_PHYSICALCPU,
+        ROOT_VOLUME_FREE,
+    };
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(16 << 30), "16 GiB");
+        assert_eq!(format_bytes(25 << 29), "12.5 GiB");
+        assert_eq!(Resource::Memory.format(8 << 30), "8 GiB of memory");
+        assert_eq!(Resource::PhysicalCores.format(8), "8 physical cores");
+    }
+
+    #[test]
+    fn test_hardware() {
+        let system = FakeSystem::new()
+            .with_value(HW_MEMSIZE, "8589934592")
+            .with_value(HW_PHYSICALCPU, "8")
+            .with_value(HW_LOGICALCPU, "8")
+            .with_value(ROOT_VOLUME_FREE, "107374182400");
+        let policy = HardwarePolicy {
+            min_memory_gib: Some(16),
+            min_physical_cores: Some(8),
+            min_logical_cores: Some(10),
+            min_free_disk_gib: Some(50),
+        };
+
+        assert!(matches!(
+            MemoryRule::new(&policy).evaluate(&system),
+            Err(Error::InsufficientHardware {
+                resource: Resource::Memory,
+                observed: 8589934592,
+                required: 17179869184,
+            })
+        ));
+        assert_eq!(
+            MemoryRule::new(&policy).details(&system)["hardware.memory"],
+            "8 GiB"
+        );
+        assert!(matches!(
+            CpuCoresRule::new(&policy).evaluate(&system),
+            Err(Error::InsufficientHardware {
+                resource: Resource::LogicalCores,
+                ..
+            })
+        ));
+        assert!(DiskSpaceRule::new(&policy).evaluate(&system).is_ok());
+
+        let unset = HardwarePolicy::default();
+        assert!(MemoryRule::new(&unset).evaluate(&FakeSystem::new()).is_ok());
+        assert!(CpuCoresRule::new(&unset)
+            .details(&FakeSystem::new())
+            .is_empty());
+        assert!(matches!(
+            DiskSpaceRule::new(&policy).evaluate(&FakeSystem::new()),
+            Err(Error::Missing(_))
+        ));
+    }
+}
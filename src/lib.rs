@@ -1,24 +1,153 @@
 //! Crate for finding bad Mac users.
 
 #![warn(missing_docs)]
-#![cfg(target_os = "macos")]
 
-use std::fmt::Display;
+use std::{fmt::Display, ops::Bound};
 
-use sysctl::{Ctl, Sysctl, SysctlError};
+#[cfg(target_os = "macos")]
+use sysctl::SysctlError;
+
+mod cpu;
+mod date;
+mod detector;
+mod hardware;
+mod matrix;
+mod model;
+mod policy;
+mod release;
+mod report;
+mod rosetta;
+mod rule;
+mod simulate;
+mod snapshot;
+mod sysctl_rule;
+mod system;
+mod version;
+mod vm;
+
+pub use cpu::{Architecture, ChipGeneration, CpuRule, ParseCpuError};
+pub use date::{Date, ParseDateError};
+pub use detector::Detector;
+pub use hardware::{CpuCoresRule, DiskSpaceRule, MemoryRule, Resource};
+pub use matrix::{Cell, ComplianceMatrix, MatrixRow};
+pub use model::{Chip, ChipTier, Family, MacModel};
+#[cfg(feature = "toml")]
+pub use policy::PolicyError;
+pub use policy::{
+    CpuPolicy, HardwarePolicy, ModelPolicy, OsPolicy, Policy, VmMode, VmPolicy, Waiver,
+};
+pub use release::{MacOsRelease, SupportStatus};
+pub use report::{Finding, Outcome, ParseSeverityError, Report, Severity, Verdict};
+pub use rosetta::{is_translated, NativeSystem, RosettaRule};
+pub use rule::{BetaRule, EndOfLifeRule, MacModelRule, PosixRule, Rule, VersionCompatRule};
+pub use simulate::{Change, Simulation, WhatIf};
+pub use snapshot::{SnapshotError, SystemSnapshot};
+pub use sysctl_rule::{Comparator, Number, SysctlRule};
+pub use system::{
+    FakeSystem, LiveSystem, SystemInfo, HW_LOGICALCPU, HW_MEMSIZE, HW_MODEL, HW_OPTIONAL_ARM64,
+    HW_PHYSICALCPU, IOPLATFORM_SERIAL_NUMBER, IOPLATFORM_UUID, KERN_HOSTNAME, KERN_HV_VMM_PRESENT,
+    KERN_OSPRODUCTVERSION, KERN_OSRELEASE, KERN_OSVERSION, MACHDEP_CPU_BRAND_STRING,
+    ROOT_VOLUME_FREE, SYSCTL_PROC_TRANSLATED,
+};
+pub use version::{BuildKind, MacOsVersion, ParseVersionError, VersionRange};
+pub use vm::{is_virtual, VmExempt, VmRule};
 
 /// Errors which will occur when checking Mac quality.
 #[derive(Debug)]
 #[non_exhaustive]
 pub enum Error {
     /// The macOS version is not compliant with POSIX.
-    NotPosix,
+    NotPosix {
+        /// The running macOS version.
+        version: MacOsVersion,
+        /// The forbidden range containing [`Error::NotPosix::version`].
+        range: Box<VersionRange>,
+    },
+    /// The macOS version is not compliant with POSIX, and the Mac shipped with a version too new
+    /// to downgrade out of the forbidden range.
+    DowngradeImpossible {
+        /// The running macOS version.
+        version: MacOsVersion,
+        /// The forbidden range containing [`Error::DowngradeImpossible::version`].
+        range: Box<VersionRange>,
+        /// The Mac model, whose [`MacModel::min_os`] is in or after the forbidden range.
+        model: &'static MacModel,
+    },
     /// The Mac model is bad.
-    BadMacModel,
-    /// Errors from [`sysctl`].
-    Sysctl(SysctlError),
+    BadMacModel {
+        /// The `hw.model` identifier of the Mac.
+        model: String,
+    },
+    /// The macOS version disagrees with the Darwin kernel, e.g. because of `SYSTEM_VERSION_COMPAT`.
+    VersionSpoofed {
+        /// The reported `kern.osproductversion`.
+        reported: String,
+        /// The Darwin kernel release, `kern.osrelease`.
+        kernel_release: String,
+        /// The build version, `kern.osversion`, if available.
+        build: Option<String>,
+    },
+    /// The macOS build is a beta or internal build, which the policy forbids.
+    BetaOs {
+        /// The build version, `kern.osversion`.
+        build: String,
+        /// The kind of the build, never [`BuildKind::Release`].
+        kind: BuildKind,
+    },
+    /// The macOS release no longer receives security updates, which the policy forbids.
+    EndOfLife {
+        /// The running macOS version.
+        version: MacOsVersion,
+    },
+    /// The Mac is a virtual machine, which the policy forbids.
+    VirtualMachine {
+        /// The `hw.model` identifier, if available.
+        model: Option<String>,
+    },
+    /// The Mac is not a virtual machine, which the policy requires.
+    PhysicalMachine {
+        /// The `hw.model` identifier, if available.
+        model: Option<String>,
+    },
+    /// The process is translated by Rosetta.
+    Translated,
+    /// The processor is not allowed by the policy.
+    BadCpu {
+        /// The architecture of the processor.
+        architecture: Architecture,
+        /// The chip, if the policy constrains chip generations.
+        chip: Option<Chip>,
+    },
+    /// The Mac has less of a hardware resource than the policy requires.
+    InsufficientHardware {
+        /// The resource.
+        resource: Resource,
+        /// The observed amount, in bytes for memory and disk space.
+        observed: u64,
+        /// The required amount, in the same unit.
+        required: u64,
+    },
+    /// Errors from reading a sysctl, only returned on macOS but present on every platform.
+    Sysctl(Box<dyn std::error::Error + Send + Sync>),
+    /// The value of a [`SysctlRule`] is not as expected.
+    UnexpectedValue {
+        /// The sysctl key, followed by the field of struct-backed values, e.g. `vm.swapusage.used`.
+        key: String,
+        /// The observed value.
+        value: String,
+        /// Description of the expected value, e.g. `at least 17179869184`.
+        expected: String,
+    },
+    /// Error when running a command to read system information.
+    Io(std::io::Error),
     /// Error when parsing macOS version.
-    ParseOsVersion,
+    ParseOsVersion(ParseVersionError),
+    /// The requested system information key is not available.
+    Missing(String),
+    /// Live system information can't be read on this platform.
+    UnsupportedPlatform,
+    /// Error reported by a custom [`Rule`].
+    Custom(Box<dyn std::error::Error + Send + Sync>),
     /// Error variant that contains multiple errors.
     Many(Vec<Self>),
 }
@@ -26,10 +155,58 @@ pub enum Error {
 impl Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Error::NotPosix => write!(f, "your macOS version is not compliant with POSIX, it is recommended to downgrade your macOS to a version prior to 14.4"),
-            Error::BadMacModel => write!(f, "you have a bad taste, sell your Mac immediately and get a MacBook Pro (13-inch, M1, 2020)"),
+            Error::NotPosix { version, range } => {
+                write!(f, "your macOS version {} is in the forbidden range {} and not compliant with POSIX, ", MacOsRelease::describe(version), range)?;
+                match (range.start(), range.end()) {
+                    (Bound::Included(start), _) => write!(f, "it is recommended to downgrade your macOS to a version prior to {}", start),
+                    (Bound::Excluded(start), _) => write!(f, "it is recommended to downgrade your macOS to {} or earlier", start),
+                    (Bound::Unbounded, Bound::Included(end)) => write!(f, "it is recommended to upgrade your macOS to a version after {}", end),
+                    (Bound::Unbounded, Bound::Excluded(end)) => write!(f, "it is recommended to upgrade your macOS to {} or later", end),
+                    (Bound::Unbounded, Bound::Unbounded) => write!(f, "it is recommended to sell your Mac"),
+                }
+            }
+            Error::DowngradeImpossible { version, range, model } => {
+                write!(f, "your macOS version {} is in the forbidden range {} and not compliant with POSIX, but your {} shipped with macOS {} and can't be downgraded, ", MacOsRelease::describe(version), range, model, MacOsRelease::describe(&model.min_os))?;
+                match range.start() {
+                    Bound::Included(start) => write!(f, "it is recommended to replace it with a Mac that shipped before {}, e.g. a MacBook Pro (13-inch, M1, 2020)", start),
+                    Bound::Excluded(start) => write!(f, "it is recommended to replace it with a Mac that shipped with {} or earlier, e.g. a MacBook Pro (13-inch, M1, 2020)", start),
+                    Bound::Unbounded => write!(f, "it is recommended to sell your Mac"),
+                }
+            }
+            Error::BadMacModel { model } => match MacModel::lookup(model) {
+                Some(model) => write!(f, "you have a bad taste, sell your {} immediately and get a MacBook Pro (13-inch, M1, 2020)", model),
+                None => write!(f, "you have a bad taste, sell your Mac ({}) immediately and get a MacBook Pro (13-inch, M1, 2020)", model),
+            },
+            Error::VersionSpoofed { reported, kernel_release, build } => {
+                write!(f, "your macOS claims to be {} but runs Darwin {}", reported, kernel_release)?;
+                if let Some(build) = build {
+                    write!(f, " (build {})", build)?;
+                }
+                write!(f, ", unset SYSTEM_VERSION_COMPAT and stop lying about your macOS version")
+            }
+            Error::BetaOs { build, kind } => write!(f, "your macOS build {} is a {} build, stop testing Apple's software for free and install a release", build, kind),
+            Error::EndOfLife { version } => write!(f, "your macOS {} no longer receives security updates, upgrade it before it gets you hacked", MacOsRelease::describe(version)),
+            Error::VirtualMachine { model } => match model {
+                Some(model) => write!(f, "your Mac is a virtual machine ({}), which the policy forbids, run on real hardware", model),
+                None => write!(f, "your Mac is a virtual machine, which the policy forbids, run on real hardware"),
+            },
+            Error::PhysicalMachine { model } => match model {
+                Some(model) => write!(f, "your Mac ({}) is not a virtual machine, which the policy requires, run in a VM", model),
+                None => write!(f, "your Mac is not a virtual machine, which the policy requires, run in a VM"),
+            },
+            Error::Translated => write!(f, "this process runs under Rosetta translation and sees a fake Intel processor, install the arm64 build"),
+            Error::BadCpu { architecture, chip } => match chip {
+                Some(chip) => write!(f, "your {} ({}) processor is not allowed by the policy, get a newer Mac", chip, architecture),
+                None => write!(f, "your {} processor is not allowed by the policy, get a newer Mac", architecture),
+            },
+            Error::InsufficientHardware { resource, observed, required } => write!(f, "your Mac only has {} but the policy requires {}, stop building on a potato", resource.format(*observed), resource.format(*required)),
             Error::Sysctl(err) => write!(f, "sysctl error: {}", err),
-            Error::ParseOsVersion => write!(f, "your macOS version looks weird and can't be parsed"),
+            Error::UnexpectedValue { key, value, expected } => write!(f, "`{}` is `{}` but should be {}", key, value, expected),
+            Error::Io(err) => write!(f, "can't read system information: {}", err),
+            Error::ParseOsVersion(err) => write!(f, "your macOS version looks weird and can't be parsed: {}", err),
+            Error::Missing(name) => write!(f, "system information `{}` is not available", name),
+            Error::UnsupportedPlatform => write!(f, "live system information can only be read on macOS"),
+            Error::Custom(err) => write!(f, "{}", err),
             Error::Many(errs) => {
                 write!(f, "multiple errors: ")?;
                 for err in errs {
@@ -41,88 +218,195 @@ impl Display for Error {
     }
 }
 
+impl Error {
+    /// Stable identifier of the error kind, e.g. `not-posix` or `bad-mac-model`.
+    ///
+    /// Violations of built-in rules share the [`Rule::id`] of the rule, except for
+    /// [`Error::DowngradeImpossible`] which is `downgrade-impossible`. Violations of every
+    /// [`SysctlRule`] are `unexpected-value`.
+    pub fn id(&self) -> &'static str {
+        match self {
+            Error::NotPosix { .. } => PosixRule::ID,
+            Error::DowngradeImpossible { .. } => "downgrade-impossible",
+            Error::BadMacModel { .. } => MacModelRule::ID,
+            Error::VersionSpoofed { .. } => VersionCompatRule::ID,
+            Error::BetaOs { .. } => BetaRule::ID,
+            Error::EndOfLife { .. } => EndOfLifeRule::ID,
+            Error::VirtualMachine { .. } | Error::PhysicalMachine { .. } => VmRule::ID,
+            Error::Translated => RosettaRule::ID,
+            Error::BadCpu { .. } => CpuRule::ID,
+            Error::InsufficientHardware { resource, .. } => match resource {
+                Resource::Memory => MemoryRule::ID,
+                Resource::PhysicalCores | Resource::LogicalCores => CpuCoresRule::ID,
+                Resource::DiskSpace => DiskSpaceRule::ID,
+            },
+            Error::Sysctl(_) => "sysctl",
+            Error::UnexpectedValue { .. } => "unexpected-value",
+            Error::Io(_) => "io",
+            Error::ParseOsVersion(_) => "parse-os-version",
+            Error::Missing(_) => "missing",
+            Error::UnsupportedPlatform => "unsupported-platform",
+            Error::Custom(_) => "custom",
+            Error::Many(_) => "many",
+        }
+    }
+
+    /// Whether the error means that system information couldn't be read or understood,
+    /// as opposed to the Mac being bad.
+    pub fn is_probe_failure(&self) -> bool {
+        match self {
+            Error::Sysctl(_)
+            | Error::Io(_)
+            | Error::ParseOsVersion(_)
+            | Error::Missing(_)
+            | Error::UnsupportedPlatform => true,
+            Error::Many(errs) => errs.iter().all(Error::is_probe_failure),
+            Error::NotPosix { .. }
+            | Error::DowngradeImpossible { .. }
+            | Error::BadMacModel { .. }
+            | Error::VersionSpoofed { .. }
+            | Error::BetaOs { .. }
+            | Error::EndOfLife { .. }
+            | Error::VirtualMachine { .. }
+            | Error::PhysicalMachine { .. }
+            | Error::Translated
+            | Error::BadCpu { .. }
+            | Error::InsufficientHardware { .. }
+            | Error::UnexpectedValue { .. }
+            | Error::Custom(_) => false,
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
 impl From<SysctlError> for Error {
     #[inline]
     fn from(value: SysctlError) -> Self {
-        Self::Sysctl(value)
+        Self::Sysctl(Box::new(value))
+    }
+}
+
+impl From<ParseVersionError> for Error {
+    #[inline]
+    fn from(value: ParseVersionError) -> Self {
+        Self::ParseOsVersion(value)
+    }
+}
+
+/// Serializes as `{"id": ..., "message": ...}`, with the nested `errors` of [`Error::Many`].
+#[cfg(feature = "serde")]
+impl serde::Serialize for Error {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("Error", 3)?;
+        state.serialize_field("id", self.id())?;
+        state.serialize_field("message", &self.to_string())?;
+        if let Error::Many(errs) = self {
+            state.serialize_field("errors", errs)?;
+        } else {
+            state.skip_field("errors")?;
+        }
+        state.end()
     }
 }
 
 impl std::error::Error for Error {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
-            Error::Sysctl(err) => Some(err),
+            Error::Io(err) => Some(err),
+            Error::ParseOsVersion(err) => Some(err),
+            Error::Sysctl(err) | Error::Custom(err) => Some(err.as_ref()),
             _ => None,
         }
     }
 }
 
-const HW_MODEL: &str = "hw.model";
-const KERN_OSPRODUCTVERSION: &str = "kern.osproductversion";
+/// Reports the outcome of every built-in check on this Mac.
+pub fn report() -> Report {
+    report_with(&LiveSystem)
+}
+
+/// Reports the outcome of every built-in check on the Mac described by `system`.
+pub fn report_with(system: &dyn SystemInfo) -> Report {
+    Detector::new().report(system)
+}
 
-/// Very bad machine.
-const PULP_MACHINE: &str = "MacBookPro16,1";
+/// Records the system information of this Mac read by the built-in checks.
+pub fn snapshot() -> SystemSnapshot {
+    Detector::new().capture(&LiveSystem)
+}
 
 /// Checks whether this Mac is bad.
 ///
 /// # Errors
 ///
-/// - Errors if macOS version is equal to or newer than __`14.4`__, which is not POSIX-compliant.
+/// - Errors if macOS version is equal to or newer than __`14.4`__, which is not POSIX-compliant,
+///   with [`Error::DowngradeImpossible`] if the Mac model shipped with 14.4 or later.
 /// - Errors if the Mac model is `MacBookPro16,1`.
+/// - Errors if the macOS version disagrees with the Darwin kernel, e.g. because of `SYSTEM_VERSION_COMPAT=1`.
+/// - Errors with a single [`Error::UnsupportedPlatform`] when not running on macOS.
 pub fn check() -> Result<(), Error> {
-    let mut errs: Vec<Error> = Vec::with_capacity(2);
-    if let Err(err) = check_posix() {
-        errs.push(err);
-    }
-    if let Err(err) = check_machine() {
-        errs.push(err);
-    }
-    if errs.is_empty() {
-        Ok(())
-    } else if let Some(err) = errs.pop().filter(|_| errs.is_empty()) {
-        Err(err)
-    } else {
-        Err(Error::Many(errs))
-    }
-}
-
-/// Checks whether macOS version is equal to or newer than __`14.4`__, which is not POSIX-compliant.
-fn check_posix() -> Result<(), Error> {
-    let ctl = Ctl::new(KERN_OSPRODUCTVERSION)?;
-    let ver_str = ctl.value_string()?;
-    let ver_split = ver_str.split('.');
-    let mut is_sonoma = false;
-    for num in ver_split {
-        if !is_sonoma {
-            match num.parse::<usize>().map_err(|_| Error::ParseOsVersion)? {
-                ..=13 => return Ok(()),
-                14 => is_sonoma = true,
-                _ => return Err(Error::NotPosix),
-            }
-        } else if let ..=3 = num.parse::<usize>().map_err(|_| Error::ParseOsVersion)? {
-            return Ok(());
-        } else {
-            return Err(Error::NotPosix);
-        }
-    }
-
-    // Can't split version string by `.` because the loop doesn't run.
-    Err(Error::ParseOsVersion)
+    check_with(&LiveSystem)
 }
 
-fn check_machine() -> Result<(), Error> {
-    let ctl = Ctl::new(HW_MODEL)?;
-    if ctl.value_string()? == PULP_MACHINE {
-        Err(Error::BadMacModel)
-    } else {
-        Ok(())
-    }
+/// Checks whether the Mac described by `system` is bad.
+///
+/// # Errors
+///
+/// Same as [`check`].
+pub fn check_with(system: &dyn SystemInfo) -> Result<(), Error> {
+    report_with(system).into_result()
 }
 
 #[cfg(test)]
 mod test {
+    use crate::{check_with, Error, FakeSystem};
+
     #[test]
+    #[cfg(target_os = "macos")]
     fn test_decency() {
         assert!(crate::check().is_ok())
     }
+
+    #[test]
+    fn test_fake_system() {
+        let good = FakeSystem::new()
+            .with_os_product_version("14.3.1")
+            .with_kernel_release("23.3.0")
+            .with_hw_model("MacBookPro17,1");
+        assert!(check_with(&good).is_ok());
+
+        let bad = FakeSystem::new()
+            .with_os_product_version("14.4")
+            .with_kernel_release("23.4.0")
+            .with_hw_model("MacBookPro16,1");
+        assert!(matches!(check_with(&bad), Err(Error::Many(errs)) if errs.len() == 2));
+
+        let weird = FakeSystem::new()
+            .with_os_product_version("Sonoma")
+            .with_hw_model("MacBookPro17,1");
+        assert!(
+            matches!(check_with(&weird), Err(Error::Many(errs)) if errs.iter().all(
+                |err| matches!(err, Error::ParseOsVersion(err) if err.input() == "Sonoma")
+            ))
+        );
+
+        let empty = FakeSystem::new();
+        assert!(matches!(check_with(&empty), Err(Error::Many(_))));
+    }
+
+    #[test]
+    #[cfg(not(target_os = "macos"))]
+    fn test_unsupported_platform() {
+        assert!(matches!(crate::check(), Err(Error::UnsupportedPlatform)));
+        let report = crate::report();
+        assert!(matches!(
+            report.findings[0].outcome,
+            crate::Outcome::Error(Error::UnsupportedPlatform)
+        ));
+        assert!(report.findings[1..]
+            .iter()
+            .all(|finding| matches!(finding.outcome, crate::Outcome::Skipped)));
+    }
 }
@@ -0,0 +1,458 @@
+//! Command-line interface running the built-in checks.
+
+use std::{path::PathBuf, process::ExitCode};
+
+use dikc_detector::{
+    Change, ComplianceMatrix, Detector, Error, Finding, LiveSystem, MacOsVersion, Outcome, Policy,
+    Report, Severity, Simulation, SystemInfo, SystemSnapshot, WhatIf,
+};
+
+/// Exit status bit set when some system information couldn't be read.
+const EXIT_PROBE_FAILURE: u8 = 1;
+/// Exit status bit set when the macOS version is not POSIX-compliant.
+const EXIT_NOT_POSIX: u8 = 2;
+/// Exit status bit set when the Mac model is bad.
+const EXIT_BAD_MAC_MODEL: u8 = 4;
+/// Exit status bit set when any other rule failed.
+const EXIT_OTHER: u8 = 8;
+/// Exit status for invalid command-line arguments.
+const EXIT_USAGE: u8 = 64;
+/// Exit status when the tool can't write its own output.
+const EXIT_IO: u8 = 74;
+
+const USAGE: &str = "\
+Usage: dikc-detector [OPTIONS]
+       dikc-detector what-if [--os <VERSION>] [--model <MODEL>] [--set <KEY=VALUE>]... [OPTIONS]
+       dikc-detector matrix [--format <FORMAT>] [--policy <FILE>]
+
+Finds bad Mac users.
+
+Commands:
+  what-if  Report which findings would appear or disappear with overridden system information
+  matrix   Print which known models pass the policy on which macOS versions
+
+What-if options:
+      --os <VERSION>          Pretend to run this macOS version, e.g. `15.1` or `15.1 (24B83)`
+      --model <MODEL>         Pretend to be this `hw.model`, with its processor, e.g. `Mac14,7`
+      --set <KEY=VALUE>       Override any system information value
+
+Matrix options:
+      --format <FORMAT>       Output format: `text` (default), `csv` or `markdown`
+
+Options:
+      --policy <FILE>         Load the policy from a TOML file (requires the `toml` feature)
+      --snapshot <FILE>       Check a saved system snapshot instead of this Mac
+      --save-snapshot <FILE>  Save a snapshot of the checked system information to a file
+      --json                  Print the report as JSON (requires the `serde` feature)
+  -h, --help                  Print help
+  -V, --version               Print version
+
+Exit status is 0 if the Mac passes (with the overrides for `what-if`), otherwise the bitwise OR
+of the following, ignoring findings below the policy's `fail_at` severity:
+  1  system information couldn't be read
+  2  the macOS version is not POSIX-compliant
+  4  the Mac model is bad
+  8  any other rule failed
+
+Invalid arguments exit with 64, and a snapshot or JSON output that can't be written with 74.
+";
+
+/// Parsed command-line options.
+#[derive(Debug, Default)]
+struct Options {
+    policy: Option<PathBuf>,
+    snapshot: Option<PathBuf>,
+    save_snapshot: Option<PathBuf>,
+    json: bool,
+    command: Command,
+}
+
+/// What the program does.
+#[derive(Debug, Default)]
+enum Command {
+    /// Checks the system.
+    #[default]
+    Check,
+    /// Checks the system with and without overrides.
+    WhatIf(WhatIf),
+    /// Prints the compliance matrix of the policy.
+    Matrix(MatrixFormat),
+}
+
+/// Output format of the `matrix` command.
+#[derive(Debug, Clone, Copy)]
+enum MatrixFormat {
+    Text,
+    Csv,
+    Markdown,
+}
+
+impl Options {
+    /// Parses the options, or returns the exit code if the program should exit right away.
+    fn parse(args: impl Iterator<Item = String>) -> Result<Self, ExitCode> {
+        let mut options = Self::default();
+        let mut args = args.peekable();
+        match args.peek().map(String::as_str) {
+            Some("what-if") => options.command = Command::WhatIf(WhatIf::new()),
+            Some("matrix") => options.command = Command::Matrix(MatrixFormat::Text),
+            _ => {}
+        }
+        if !matches!(options.command, Command::Check) {
+            args.next();
+        }
+        while let Some(arg) = args.next() {
+            match (arg.as_str(), &mut options.command) {
+                ("--os", Command::WhatIf(what_if)) => {
+                    let version = value_arg(&arg, &mut args)?;
+                    let version = version
+                        .parse::<MacOsVersion>()
+                        .map_err(|err| usage_error(format_args!("`{}`: {}", arg, err)))?;
+                    *what_if = std::mem::take(what_if).os_version(version);
+                }
+                ("--model", Command::WhatIf(what_if)) => {
+                    *what_if = std::mem::take(what_if).hw_model(value_arg(&arg, &mut args)?);
+                }
+                ("--set", Command::WhatIf(what_if)) => {
+                    let value = value_arg(&arg, &mut args)?;
+                    let Some((name, value)) = value.split_once('=') else {
+                        return Err(usage_error(format_args!(
+                            "`{}` requires KEY=VALUE, got `{}`",
+                            arg, value
+                        )));
+                    };
+                    *what_if = std::mem::take(what_if).value(name.trim(), value.trim());
+                }
+                ("--format", Command::Matrix(format)) => {
+                    *format = match value_arg(&arg, &mut args)?.as_str() {
+                        "text" => MatrixFormat::Text,
+                        "csv" => MatrixFormat::Csv,
+                        "markdown" | "md" => MatrixFormat::Markdown,
+                        other => {
+                            return Err(usage_error(format_args!(
+                                "unknown matrix format `{}`",
+                                other
+                            )))
+                        }
+                    };
+                }
+                ("--json" | "--snapshot" | "--save-snapshot", Command::Matrix(_)) => {
+                    return Err(usage_error(format_args!(
+                        "`{}` can't be used with `matrix`",
+                        arg
+                    )))
+                }
+                ("--policy", _) => options.policy = Some(path_arg(&arg, &mut args)?),
+                ("--snapshot", _) => options.snapshot = Some(path_arg(&arg, &mut args)?),
+                ("--save-snapshot", _) => options.save_snapshot = Some(path_arg(&arg, &mut args)?),
+                ("--json", _) => options.json = true,
+                ("-h" | "--help", _) => {
+                    print!("{}", USAGE);
+                    return Err(ExitCode::SUCCESS);
+                }
+                ("-V" | "--version", _) => {
+                    println!("dikc-detector {}", env!("CARGO_PKG_VERSION"));
+                    return Err(ExitCode::SUCCESS);
+                }
+                (_, _) => return Err(usage_error(format_args!("unexpected argument `{}`", arg))),
+            }
+        }
+        Ok(options)
+    }
+}
+
+fn path_arg(option: &str, args: &mut impl Iterator<Item = String>) -> Result<PathBuf, ExitCode> {
+    args.next()
+        .map(PathBuf::from)
+        .ok_or_else(|| usage_error(format_args!("`{}` requires a file", option)))
+}
+
+fn value_arg(option: &str, args: &mut impl Iterator<Item = String>) -> Result<String, ExitCode> {
+    args.next()
+        .ok_or_else(|| usage_error(format_args!("`{}` requires a value", option)))
+}
+
+fn usage_error(msg: std::fmt::Arguments) -> ExitCode {
+    eprintln!("error: {}\n\n{}", msg, USAGE);
+    ExitCode::from(EXIT_USAGE)
+}
+
+fn main() -> ExitCode {
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(code) => return code,
+    };
+
+    let policy = match &options.policy {
+        Some(path) => match load_policy(path) {
+            Ok(policy) => policy,
+            Err(code) => return code,
+        },
+        None => Policy::default(),
+    };
+
+    if let Command::Matrix(format) = options.command {
+        let matrix = ComplianceMatrix::new(&policy);
+        match format {
+            MatrixFormat::Text => print!("{}", matrix),
+            MatrixFormat::Csv => print!("{}", matrix.to_csv()),
+            MatrixFormat::Markdown => print!("{}", matrix.to_markdown()),
+        }
+        return ExitCode::SUCCESS;
+    }
+
+    let snapshot = match &options.snapshot {
+        Some(path) => match SystemSnapshot::load(path) {
+            Ok(snapshot) => Some(snapshot),
+            Err(err) => {
+                eprintln!("error: {}: {}", path.display(), err);
+                return ExitCode::from(EXIT_USAGE);
+            }
+        },
+        None => None,
+    };
+    let system: &dyn SystemInfo = match &snapshot {
+        Some(snapshot) => snapshot,
+        None => &LiveSystem,
+    };
+
+    let detector = Detector::with_policy(&policy);
+    if let Some(path) = &options.save_snapshot {
+        if let Err(err) = detector.capture(system).save(path) {
+            eprintln!("error: {}: {}", path.display(), err);
+            return ExitCode::from(EXIT_IO);
+        }
+    }
+
+    if let Command::WhatIf(what_if) = &options.command {
+        let simulation = detector.simulate(system, what_if);
+        if options.json {
+            if let Err(code) = print_json(&simulation) {
+                return code;
+            }
+        } else {
+            print_simulation(what_if, &simulation);
+        }
+        return ExitCode::from(exit_status(&simulation.simulated, detector.threshold()));
+    }
+
+    let report = detector.report(system);
+    if options.json {
+        if let Err(code) = print_json(&report) {
+            return code;
+        }
+    } else {
+        print_report(&report, detector.threshold());
+    }
+    ExitCode::from(exit_status(&report, detector.threshold()))
+}
+
+#[cfg(feature = "toml")]
+fn load_policy(path: &std::path::Path) -> Result<Policy, ExitCode> {
+    Policy::from_path(path).map_err(|err| {
+        eprintln!("error: {}: {}", path.display(), err);
+        ExitCode::from(EXIT_USAGE)
+    })
+}
+
+#[cfg(not(feature = "toml"))]
+fn load_policy(_path: &std::path::Path) -> Result<Policy, ExitCode> {
+    Err(usage_error(format_args!(
+        "`--policy` requires building with the `toml` feature"
+    )))
+}
+
+#[cfg(feature = "serde")]
+fn print_json(value: &impl serde::Serialize) -> Result<(), ExitCode> {
+    match serde_json::to_string_pretty(value) {
+        Ok(json) => {
+            println!("{}", json);
+            Ok(())
+        }
+        Err(err) => {
+            eprintln!("error: {}", err);
+            Err(ExitCode::from(EXIT_IO))
+        }
+    }
+}
+
+#[cfg(not(feature = "serde"))]
+fn print_json<T>(_value: &T) -> Result<(), ExitCode> {
+    Err(usage_error(format_args!(
+        "`--json` requires building with the `serde` feature"
+    )))
+}
+
+fn print_report(report: &Report, threshold: Severity) {
+    for finding in &report.findings {
+        println!("{}: {}", finding.rule_id, OutcomeDisplay(finding));
+        for (name, value) in &finding.details {
+            println!("  {}: {}", name, value);
+        }
+    }
+    println!("verdict: {}", report.verdict_at(threshold));
+}
+
+fn print_simulation(what_if: &WhatIf, simulation: &Simulation) {
+    if what_if.is_empty() {
+        println!("what-if: no overrides");
+    } else {
+        println!("what-if: {}", what_if);
+    }
+    let changes = simulation.changes();
+    if changes.is_empty() {
+        println!("no findings would change");
+    }
+    for change in changes {
+        match change {
+            Change::Appeared(finding) => print_change("appears", finding),
+            Change::Disappeared(finding) => print_change("disappears", finding),
+            Change::Changed { actual, simulated } => {
+                print_change("changes from", actual);
+                print_change("to", simulated);
+            }
+        }
+    }
+}
+
+fn print_change(change: &str, finding: &Finding) {
+    println!(
+        "{}: {} {}",
+        finding.rule_id,
+        change,
+        OutcomeDisplay(finding)
+    );
+}
+
+/// Displays the outcome of a [`Finding`] as `pass`, `fail [<severity>]: <message>`,
+/// `error [<severity>]: <message>` or `skipped`.
+struct OutcomeDisplay<'a>(&'a Finding);
+
+impl std::fmt::Display for OutcomeDisplay<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let severity = self.0.severity;
+        match &self.0.outcome {
+            Outcome::Pass => write!(f, "pass"),
+            Outcome::Fail(err) => write!(f, "fail [{}]: {}", severity, err),
+            Outcome::Error(err) => write!(f, "error [{}]: {}", severity, err),
+            Outcome::Waived(err) => {
+                write!(f, "waived [{}]: {}", severity, err)?;
+                if let Some(waiver) = &self.0.waiver {
+                    write!(f, " (until {}: {})", waiver.expires, waiver.justification)?;
+                }
+                Ok(())
+            }
+            Outcome::Skipped => write!(f, "skipped"),
+            outcome => write!(f, "{:?}", outcome),
+        }
+    }
+}
+
+fn exit_status(report: &Report, threshold: Severity) -> u8 {
+    report
+        .findings
+        .iter()
+        .filter(|finding| finding.severity >= threshold)
+        .map(|finding| match &finding.outcome {
+            Outcome::Error(_) => EXIT_PROBE_FAILURE,
+            Outcome::Fail(Error::NotPosix { .. } | Error::DowngradeImpossible { .. }) => {
+                EXIT_NOT_POSIX
+            }
+            Outcome::Fail(Error::BadMacModel { .. }) => EXIT_BAD_MAC_MODEL,
+            Outcome::Fail(_) => EXIT_OTHER,
+            _ => 0,
+        })
+        .fold(0, |status, bit| status | bit)
+}
+
+#[cfg(test)]
+mod test {
+    use std::process::ExitCode;
+
+    use dikc_detector::{Detector, FakeSystem, Policy, Severity};
+
+    use super::{exit_status, Command, Options, EXIT_USAGE};
+
+    fn parse(args: &[&str]) -> Result<Options, ExitCode> {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    /// The exit code of a parse error, formatted since `ExitCode` isn't comparable on Rust 1.80.
+    fn exit_code(args: &[&str]) -> Option<String> {
+        parse(args).err().map(|code| format!("{:?}", code))
+    }
+
+    #[test]
+    fn test_parse() {
+        let options = parse(&["--json", "--policy", "policy.toml"]).unwrap();
+        assert!(options.json);
+        assert_eq!(options.policy.unwrap().to_str(), Some("policy.toml"));
+        assert!(matches!(options.command, Command::Check));
+
+        let options = parse(&["what-if", "--os", "15.1", "--set", "hw.memsize=8"]).unwrap();
+        let Command::WhatIf(what_if) = options.command else {
+            panic!("expected what-if");
+        };
+        assert_eq!(
+            what_if.iter().find(|(name, _)| *name == "hw.memsize"),
+            Some(("hw.memsize", Some("8")))
+        );
+
+        let usage = Some(format!("{:?}", ExitCode::from(EXIT_USAGE)));
+        assert_eq!(exit_code(&["--frobnicate"]), usage);
+        assert_eq!(exit_code(&["--policy"]), usage);
+        assert_eq!(exit_code(&["what-if", "--os"]), usage);
+        assert_eq!(exit_code(&["what-if", "--os", "Sonoma"]), usage);
+        assert_eq!(exit_code(&["what-if", "--set", "hw.memsize"]), usage);
+        assert_eq!(exit_code(&["--os", "15.1"]), usage);
+        assert_eq!(exit_code(&["matrix", "--format", "pdf"]), usage);
+        assert_eq!(exit_code(&["matrix", "--json"]), usage);
+        assert_eq!(exit_code(&["matrix", "--snapshot", "mac.json"]), usage);
+        assert_eq!(
+            exit_code(&["--help"]),
+            Some(format!("{:?}", ExitCode::SUCCESS))
+        );
+    }
+
+    #[test]
+    fn test_exit_status() {
+        let system = |version: &str, kernel: &str, model: &str| {
+            FakeSystem::new()
+                .with_os_product_version(version)
+                .with_kernel_release(kernel)
+                .with_hw_model(model)
+        };
+        let status = |detector: &Detector, system: &FakeSystem| {
+            exit_status(&detector.report(system), detector.threshold())
+        };
+        let detector = Detector::new();
+        assert_eq!(
+            status(&detector, &system("14.3.1", "23.3.0", "MacBookPro17,1")),
+            0
+        );
+        assert_eq!(status(&detector, &FakeSystem::new()), 1);
+        assert_eq!(
+            status(&detector, &system("14.4.1", "23.4.0", "MacBookPro17,1")),
+            2
+        );
+        assert_eq!(
+            status(&detector, &system("14.3.1", "23.3.0", "MacBookPro16,1")),
+            4
+        );
+        assert_eq!(
+            status(&detector, &system("10.16", "23.3.0", "MacBookPro17,1")),
+            8
+        );
+        assert_eq!(
+            status(&detector, &system("14.4.1", "23.4.0", "MacBookPro16,1")),
+            2 | 4
+        );
+
+        // `eol-os` is a warning, below the default threshold.
+        let mut policy = Policy::default();
+        policy.os.forbid_end_of_life = true;
+        let end_of_life = system("12.7.6", "21.6.0", "MacBookPro17,1");
+        assert_eq!(status(&Detector::with_policy(&policy), &end_of_life), 0);
+        policy.fail_at = Severity::Warning;
+        assert_eq!(status(&Detector::with_policy(&policy), &end_of_life), 8);
+    }
+}
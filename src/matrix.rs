@@ -0,0 +1,332 @@
+//! Compliance of every known model and macOS version with a policy.
+
+use std::{fmt::Display, ops::Bound};
+
+use crate::{Detector, FakeSystem, MacModel, MacOsRelease, MacOsVersion, Outcome, Policy, WhatIf};
+
+/// Outcome of the rules for one model on one macOS version.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Cell {
+    /// No rule fails.
+    Pass,
+    /// The IDs of the rules failing at or above [`Detector::threshold`], in rule order.
+    Fail(Vec<String>),
+    /// No rule fails, but the IDs of these rules at or above [`Detector::threshold`] couldn't be
+    /// evaluated from the model and version alone, in rule order.
+    Unknown(Vec<String>),
+    /// The model can't run the release of the version, see [`MacModel::min_os`] and
+    /// [`MacModel::max_os`].
+    Unsupported,
+}
+
+impl Display for Cell {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Cell::Pass => write!(f, "pass"),
+            Cell::Fail(rules) => write!(f, "fail ({})", rules.join(", ")),
+            Cell::Unknown(rules) => write!(f, "unknown ({})", rules.join(", ")),
+            Cell::Unsupported => write!(f, "n/a"),
+        }
+    }
+}
+
+/// One model's row of a [`ComplianceMatrix`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct MatrixRow {
+    /// The model.
+    pub model: &'static MacModel,
+    /// One cell per [`ComplianceMatrix::versions`].
+    pub cells: Vec<Cell>,
+}
+
+/// Outcome of the rules for every known [`MacModel`] on a set of macOS versions.
+///
+/// Each cell runs the rules on a system with just the `hw.model`, `kern.osproductversion` and
+/// `kern.osrelease` of the model and version, plus the processor of the model as set by
+/// [`WhatIf::hw_model`]. Rules which need further system information, e.g. memory or sysctl
+/// rules, make the cell [`Cell::Unknown`] unless another rule fails. Models which shipped with a later update of the same
+/// release, e.g. 10.10.2 for a 10.10 column, are evaluated on the version they shipped with.
+///
+/// # Example
+///
+/// ```
+/// use dikc_detector::{Cell, ComplianceMatrix, Policy};
+///
+/// let matrix = ComplianceMatrix::new(&Policy::default());
+/// let row = matrix.rows.iter().find(|row| row.model.identifier == "MacBookPro17,1").unwrap();
+/// let sonoma = matrix.versions.iter().position(|v| v.to_string() == "14.4").unwrap();
+/// assert_eq!(row.cells[sonoma], Cell::Fail(vec!["not-posix".to_owned()]));
+/// println!("{}", matrix.to_markdown());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ComplianceMatrix {
+    /// The columns, oldest first.
+    pub versions: Vec<MacOsVersion>,
+    /// One row per model, in [`MacModel::all`] order.
+    pub rows: Vec<MatrixRow>,
+}
+
+impl ComplianceMatrix {
+    /// Evaluates the built-in rules configured by `policy` on the first version of every known
+    /// [`MacOsRelease`] and every version bounding a range of [`OsPolicy`](crate::OsPolicy).
+    pub fn new(policy: &Policy) -> Self {
+        let bounds = policy
+            .os
+            .forbid
+            .iter()
+            .chain(&policy.os.allow)
+            .flat_map(|range| [range.start(), range.end()])
+            .filter_map(|bound| match bound {
+                Bound::Included(version) | Bound::Excluded(version) => Some(version.clone()),
+                Bound::Unbounded => None,
+            });
+        let versions = MacOsRelease::all()
+            .iter()
+            .map(|release| release.version.clone())
+            .chain(bounds);
+        Self::evaluate(&Detector::with_policy(policy), versions)
+    }
+
+    /// Evaluates the rules of `detector` on `versions`.
+    pub fn evaluate(detector: &Detector, versions: impl IntoIterator<Item = MacOsVersion>) -> Self {
+        let mut versions: Vec<_> = versions.into_iter().collect();
+        versions.sort();
+        versions.dedup();
+
+        let system = FakeSystem::new();
+        let rows = MacModel::all()
+            .iter()
+            .map(|model| {
+                let cells = versions
+                    .iter()
+                    .map(|version| {
+                        let version = if model.can_run(version) {
+                            version
+                        } else if same_release(version, &model.min_os) {
+                            &model.min_os
+                        } else {
+                            return Cell::Unsupported;
+                        };
+                        let what_if = WhatIf::new()
+                            .os_version(version.clone())
+                            .hw_model(model.identifier);
+                        let (mut failing, mut unknown) = (Vec::new(), Vec::new());
+                        for finding in detector.report(&what_if.apply(&system)).findings {
+                            if finding.severity < detector.threshold() {
+                                continue;
+                            }
+                            match finding.outcome {
+                                Outcome::Fail(_) => failing.push(finding.rule_id),
+                                Outcome::Error(_) => unknown.push(finding.rule_id),
+                                _ => {}
+                            }
+                        }
+                        if !failing.is_empty() {
+                            Cell::Fail(failing)
+                        } else if !unknown.is_empty() {
+                            Cell::Unknown(unknown)
+                        } else {
+                            Cell::Pass
+                        }
+                    })
+                    .collect();
+                MatrixRow { model, cells }
+            })
+            .collect();
+        Self { versions, rows }
+    }
+
+    /// Renders the matrix as CSV, with a `model` and a `name` column before the versions.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("model,name");
+        for version in &self.versions {
+            csv.push(',');
+            csv.push_str(&csv_field(&version.to_string()));
+        }
+        csv.push('\n');
+        for row in &self.rows {
+            csv.push_str(&csv_field(row.model.identifier));
+            csv.push(',');
+            csv.push_str(&csv_field(row.model.name));
+            for cell in &row.cells {
+                csv.push(',');
+                csv.push_str(&csv_field(&cell.to_string()));
+            }
+            csv.push('\n');
+        }
+        csv
+    }
+
+    /// Renders the matrix as a Markdown table.
+    pub fn to_markdown(&self) -> String {
+        let mut md = String::from("| Model |");
+        for version in &self.versions {
+            md.push_str(&format!(" {} |", MacOsRelease::describe(version)));
+        }
+        md.push_str("\n| --- |");
+        md.push_str(&" --- |".repeat(self.versions.len()));
+        md.push('\n');
+        for row in &self.rows {
+            md.push_str(&format!(
+                "| {} |",
+                row.model.to_string().replace('|', "\\|")
+            ));
+            for cell in &row.cells {
+                md.push_str(&format!(" {} |", cell));
+            }
+            md.push('\n');
+        }
+        md
+    }
+}
+
+/// Renders the matrix as an aligned plain-text table, with one row per model identifier.
+impl Display for ComplianceMatrix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let header: Vec<String> = std::iter::once("model".to_owned())
+            .chain(self.versions.iter().map(ToString::to_string))
+            .collect();
+        let rows: Vec<Vec<String>> = self
+            .rows
+            .iter()
+            .map(|row| {
+                std::iter::once(row.model.identifier.to_owned())
+                    .chain(row.cells.iter().map(ToString::to_string))
+                    .collect()
+            })
+            .collect();
+        let widths: Vec<usize> = (0..header.len())
+            .map(|i| {
+                std::iter::once(&header)
+                    .chain(&rows)
+                    .map(|row| row[i].len())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        for row in std::iter::once(&header).chain(&rows) {
+            let line: Vec<String> = row
+                .iter()
+                .zip(&widths)
+                .map(|(field, width)| format!("{:width$}", field, width = width))
+                .collect();
+            writeln!(f, "{}", line.join("  ").trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether `a` and `b` belong to the same known [`MacOsRelease`].
+fn same_release(a: &MacOsVersion, b: &MacOsVersion) -> bool {
+    match (MacOsRelease::for_version(a), MacOsRelease::for_version(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Quotes `field` if it contains a comma, quote or newline.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Cell, ComplianceMatrix};
+    use crate::{Architecture, HardwarePolicy, MacModel, MacOsVersion, Policy};
+
+    #[test]
+    fn test_matrix() {
+        let matrix = ComplianceMatrix::new(&Policy::default());
+        let column = |version: &str| {
+            let version: MacOsVersion = version.parse().unwrap();
+            matrix.versions.iter().position(|v| *v == version).unwrap()
+        };
+        let row = |model: &str| {
+            &matrix
+                .rows
+                .iter()
+                .find(|row| row.model.identifier == model)
+                .unwrap()
+                .cells
+        };
+        assert_eq!(matrix.rows.len(), MacModel::all().len());
+        assert_eq!(row("MacBookPro17,1")[column("14.0")], Cell::Pass);
+        assert_eq!(
+            row("MacBookPro17,1")[column("14.4")],
+            Cell::Fail(vec!["not-posix".to_owned()])
+        );
+        assert_eq!(row("MacBookPro17,1")[column("10.15")], Cell::Unsupported);
+        assert_eq!(row("MacBook8,1")[column("11.0")], Cell::Pass);
+        assert_eq!(row("MacBook8,1")[column("14.0")], Cell::Unsupported);
+        assert_eq!(row("MacBook8,1")[column("26.0")], Cell::Unsupported);
+        assert_eq!(
+            row("MacBookPro16,1")[column("10.15")],
+            Cell::Fail(vec!["bad-mac-model".to_owned()])
+        );
+    }
+
+    #[test]
+    fn test_policies() {
+        let mut policy = Policy::default();
+        policy.cpu.require_arch = vec![Architecture::Arm64];
+        let matrix = ComplianceMatrix::new(&policy);
+        let column = matrix
+            .versions
+            .iter()
+            .position(|v| v.to_string() == "14.0")
+            .unwrap();
+        let row = |model: &str| {
+            &matrix
+                .rows
+                .iter()
+                .find(|row| row.model.identifier == model)
+                .unwrap()
+                .cells
+        };
+        assert_eq!(
+            row("MacBookAir9,1")[column],
+            Cell::Fail(vec!["bad-cpu".to_owned()])
+        );
+        assert_eq!(row("MacBookPro17,1")[column], Cell::Pass);
+
+        policy.hardware = HardwarePolicy {
+            min_memory_gib: Some(16),
+            ..HardwarePolicy::default()
+        };
+        let matrix = ComplianceMatrix::new(&policy);
+        let row = matrix
+            .rows
+            .iter()
+            .find(|row| row.model.identifier == "MacBookPro17,1")
+            .unwrap();
+        assert_eq!(
+            row.cells[column],
+            Cell::Unknown(vec!["low-memory".to_owned()])
+        );
+        assert_eq!(row.cells[column].to_string(), "unknown (low-memory)");
+    }
+
+    #[test]
+    fn test_render() {
+        let matrix = ComplianceMatrix::new(&Policy::default());
+        let csv = matrix.to_csv();
+        assert!(csv.starts_with("model,name,10.9,"));
+        assert!(csv.contains("\n\"MacBookPro17,1\",\"MacBook Pro (13-inch, M1, 2020)\",n/a,"));
+        assert!(csv.contains(",fail (not-posix),"));
+        assert!(csv.contains(",\"fail (not-posix, bad-mac-model)\""));
+
+        let md = matrix.to_markdown();
+        assert!(md.starts_with("| Model | Mavericks 10.9 |"));
+        assert_eq!(md.lines().count(), MacModel::all().len() + 2);
+
+        let text = matrix.to_string();
+        assert!(text.lines().nth(1).unwrap().starts_with("MacBook8,1 "));
+    }
+}
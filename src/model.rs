@@ -0,0 +1,374 @@
+//! Database of Mac hardware models.
+
+use std::fmt::Display;
+
+use self::{
+    ChipTier::{Base, Max, Pro, Ultra},
+    Family::*,
+};
+use crate::MacOsVersion;
+
+/// Product family of a Mac.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "kebab-case")
+)]
+#[non_exhaustive]
+pub enum Family {
+    /// MacBook.
+    MacBook,
+    /// MacBook Air.
+    MacBookAir,
+    /// MacBook Pro.
+    MacBookPro,
+    /// Mac mini.
+    MacMini,
+    /// iMac.
+    IMac,
+    /// iMac Pro.
+    IMacPro,
+    /// Mac Studio.
+    MacStudio,
+    /// Mac Pro.
+    MacPro,
+}
+
+impl Display for Family {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Family::MacBook => "MacBook",
+            Family::MacBookAir => "MacBook Air",
+            Family::MacBookPro => "MacBook Pro",
+            Family::MacMini => "Mac mini",
+            Family::IMac => "iMac",
+            Family::IMacPro => "iMac Pro",
+            Family::MacStudio => "Mac Studio",
+            Family::MacPro => "Mac Pro",
+        })
+    }
+}
+
+/// Tier of an Apple silicon chip within its generation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum ChipTier {
+    /// The base chip, e.g. M1.
+    Base,
+    /// Pro, e.g. M1 Pro.
+    Pro,
+    /// Max, e.g. M1 Max.
+    Max,
+    /// Ultra, e.g. M1 Ultra.
+    Ultra,
+}
+
+/// Processor of a Mac.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum Chip {
+    /// An Intel processor.
+    Intel,
+    /// An Apple silicon chip.
+    AppleSilicon {
+        /// Generation, e.g. `2` for M2.
+        generation: u8,
+        /// Tier within the generation.
+        tier: ChipTier,
+    },
+}
+
+impl Chip {
+    const fn m(generation: u8, tier: ChipTier) -> Self {
+        Chip::AppleSilicon { generation, tier }
+    }
+
+    /// Parses the processor brand (`machdep.cpu.brand_string`), e.g. `Apple M2 Pro` or
+    /// `Intel(R) Core(TM) i9-9880H CPU @ 2.30GHz`.
+    pub fn from_brand(brand: &str) -> Option<Self> {
+        if brand.starts_with("Intel") {
+            return Some(Chip::Intel);
+        }
+        let chip = brand.strip_prefix("Apple M")?;
+        let (generation, tier) = chip.split_once(' ').unwrap_or((chip, ""));
+        let tier = match tier.trim() {
+            "" => Base,
+            "Pro" => Pro,
+            "Max" => Max,
+            "Ultra" => Ultra,
+            _ => return None,
+        };
+        Some(Chip::m(generation.parse().ok()?, tier))
+    }
+}
+
+impl Display for Chip {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Chip::Intel => write!(f, "Intel"),
+            Chip::AppleSilicon { generation, tier } => {
+                write!(f, "M{}", generation)?;
+                match tier {
+                    ChipTier::Base => Ok(()),
+                    ChipTier::Pro => write!(f, " Pro"),
+                    ChipTier::Max => write!(f, " Max"),
+                    ChipTier::Ultra => write!(f, " Ultra"),
+                }
+            }
+        }
+    }
+}
+
+/// A Mac hardware model, identified by its `hw.model` identifier.
+///
+/// # Example
+///
+/// ```
+/// use dikc_detector::{Chip, MacModel};
+///
+/// let model = MacModel::lookup("MacBookPro16,1").unwrap();
+/// assert_eq!(model.name, "MacBook Pro (16-inch, 2019)");
+/// assert_eq!(model.chip, Chip::Intel);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct MacModel {
+    /// `hw.model` identifier, e.g. `MacBookPro16,1`.
+    pub identifier: &'static str,
+    /// Marketing name, e.g. `MacBook Pro (16-inch, 2019)`.
+    pub name: &'static str,
+    /// Year of introduction.
+    pub year: u16,
+    /// Product family.
+    pub family: Family,
+    /// Processor.
+    pub chip: Chip,
+    /// Oldest macOS version the model shipped with, which is the oldest it can run.
+    pub min_os: MacOsVersion,
+    /// First version of the newest release the model can run, e.g. `12.0` for Monterey, or
+    /// `None` if it runs the latest known release.
+    pub max_os: Option<MacOsVersion>,
+}
+
+impl MacModel {
+    /// Looks up the model with the `hw.model` identifier `identifier`.
+    pub fn lookup(identifier: &str) -> Option<&'static MacModel> {
+        MODELS.iter().find(|model| model.identifier == identifier)
+    }
+
+    /// Every known model.
+    pub fn all() -> &'static [MacModel] {
+        MODELS
+    }
+
+    /// Whether the model can run `version`, i.e. whether it is not older than [`MacModel::min_os`]
+    /// and its release is not newer than [`MacModel::max_os`].
+    ///
+    /// Rapid Security Responses and builds are ignored.
+    pub fn can_run(&self, version: &MacOsVersion) -> bool {
+        (version.major, version.minor, version.patch)
+            >= (self.min_os.major, self.min_os.minor, self.min_os.patch)
+            && self
+                .max_os
+                .as_ref()
+                .map_or(true, |max_os| release_key(version) <= release_key(max_os))
+    }
+}
+
+/// Orders versions by release, i.e. by major version, and by minor version before macOS 11.
+fn release_key(version: &MacOsVersion) -> (u32, u32) {
+    match version.major {
+        10 => (10, version.minor),
+        major => (major, 0),
+    }
+}
+
+impl Display for MacModel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.name, self.identifier)
+    }
+}
+
+const fn model(
+    identifier: &'static str,
+    name: &'static str,
+    year: u16,
+    family: Family,
+    chip: Chip,
+    min_os: MacOsVersion,
+    max_os: Option<MacOsVersion>,
+) -> MacModel {
+    MacModel {
+        identifier,
+        name,
+        year,
+        family,
+        chip,
+        min_os,
+        max_os,
+    }
+}
+
+const fn os(major: u32, minor: u32, patch: u32) -> MacOsVersion {
+    MacOsVersion::new(major, minor, patch)
+}
+
+const INTEL: Chip = Chip::Intel;
+
+#[rustfmt::skip]
+static MODELS: &[MacModel] = &[
+    model("MacBook8,1", "MacBook (Retina, 12-inch, Early 2015)", 2015, MacBook, INTEL, os(10, 10, 2), Some(os(11, 0, 0))),
+    model("MacBook9,1", "MacBook (Retina, 12-inch, Early 2016)", 2016, MacBook, INTEL, os(10, 11, 4), Some(os(12, 0, 0))),
+    model("MacBook10,1", "MacBook (Retina, 12-inch, 2017)", 2017, MacBook, INTEL, os(10, 12, 5), Some(os(13, 0, 0))),
+
+    model("MacBookAir7,1", "MacBook Air (11-inch, Early 2015)", 2015, MacBookAir, INTEL, os(10, 10, 2), Some(os(12, 0, 0))),
+    model("MacBookAir7,2", "MacBook Air (13-inch, Early 2015)", 2015, MacBookAir, INTEL, os(10, 10, 2), Some(os(12, 0, 0))),
+    model("MacBookAir8,1", "MacBook Air (Retina, 13-inch, 2018)", 2018, MacBookAir, INTEL, os(10, 14, 1), Some(os(14, 0, 0))),
+    model("MacBookAir8,2", "MacBook Air (Retina, 13-inch, 2019)", 2019, MacBookAir, INTEL, os(10, 14, 5), Some(os(14, 0, 0))),
+    model("MacBookAir9,1", "MacBook Air (Retina, 13-inch, 2020)", 2020, MacBookAir, INTEL, os(10, 15, 3), Some(os(15, 0, 0))),
+    model("MacBookAir10,1", "MacBook Air (M1, 2020)", 2020, MacBookAir, Chip::m(1, Base), os(11, 0, 0), None),
+    model("Mac14,2", "MacBook Air (M2, 2022)", 2022, MacBookAir, Chip::m(2, Base), os(12, 4, 0), None),
+    model("Mac14,15", "MacBook Air (15-inch, M2, 2023)", 2023, MacBookAir, Chip::m(2, Base), os(13, 4, 0), None),
+    model("Mac15,12", "MacBook Air (13-inch, M3, 2024)", 2024, MacBookAir, Chip::m(3, Base), os(14, 4, 0), None),
+    model("Mac15,13", "MacBook Air (15-inch, M3, 2024)", 2024, MacBookAir, Chip::m(3, Base), os(14, 4, 0), None),
+    model("Mac16,12", "MacBook Air (13-inch, M4, 2025)", 2025, MacBookAir, Chip::m(4, Base), os(15, 3, 0), None),
+    model("Mac16,13", "MacBook Air (15-inch, M4, 2025)", 2025, MacBookAir, Chip::m(4, Base), os(15, 3, 0), None),
+
+    model("MacBookPro11,4", "MacBook Pro (Retina, 15-inch, Mid 2015)", 2015, MacBookPro, INTEL, os(10, 10, 3), Some(os(12, 0, 0))),
+    model("MacBookPro11,5", "MacBook Pro (Retina, 15-inch, Mid 2015)", 2015, MacBookPro, INTEL, os(10, 10, 3), Some(os(12, 0, 0))),
+    model("MacBookPro12,1", "MacBook Pro (Retina, 13-inch, Early 2015)", 2015, MacBookPro, INTEL, os(10, 10, 2), Some(os(12, 0, 0))),
+    model("MacBookPro13,1", "MacBook Pro (13-inch, 2016, Two Thunderbolt 3 ports)", 2016, MacBookPro, INTEL, os(10, 12, 1), Some(os(12, 0, 0))),
+    model("MacBookPro13,2", "MacBook Pro (13-inch, 2016, Four Thunderbolt 3 ports)", 2016, MacBookPro, INTEL, os(10, 12, 1), Some(os(12, 0, 0))),
+    model("MacBookPro13,3", "MacBook Pro (15-inch, 2016)", 2016, MacBookPro, INTEL, os(10, 12, 1), Some(os(12, 0, 0))),
+    model("MacBookPro14,1", "MacBook Pro (13-inch, 2017, Two Thunderbolt 3 ports)", 2017, MacBookPro, INTEL, os(10, 12, 5), Some(os(13, 0, 0))),
+    model("MacBookPro14,2", "MacBook Pro (13-inch, 2017, Four Thunderbolt 3 ports)", 2017, MacBookPro, INTEL, os(10, 12, 5), Some(os(13, 0, 0))),
+    model("MacBookPro14,3", "MacBook Pro (15-inch, 2017)", 2017, MacBookPro, INTEL, os(10, 12, 5), Some(os(13, 0, 0))),
+    model("MacBookPro15,1", "MacBook Pro (15-inch, 2018)", 2018, MacBookPro, INTEL, os(10, 13, 6), Some(os(15, 0, 0))),
+    model("MacBookPro15,2", "MacBook Pro (13-inch, 2018, Four Thunderbolt 3 ports)", 2018, MacBookPro, INTEL, os(10, 13, 6), Some(os(15, 0, 0))),
+    model("MacBookPro15,4", "MacBook Pro (13-inch, 2019, Two Thunderbolt 3 ports)", 2019, MacBookPro, INTEL, os(10, 14, 5), Some(os(15, 0, 0))),
+    model("MacBookPro16,1", "MacBook Pro (16-inch, 2019)", 2019, MacBookPro, INTEL, os(10, 15, 1), None),
+    model("MacBookPro16,2", "MacBook Pro (13-inch, 2020, Four Thunderbolt 3 ports)", 2020, MacBookPro, INTEL, os(10, 15, 4), None),
+    model("MacBookPro16,3", "MacBook Pro (13-inch, 2020, Two Thunderbolt 3 ports)", 2020, MacBookPro, INTEL, os(10, 15, 4), Some(os(15, 0, 0))),
+    model("MacBookPro16,4", "MacBook Pro (16-inch, 2019)", 2019, MacBookPro, INTEL, os(10, 15, 5), None),
+    model("MacBookPro17,1", "MacBook Pro (13-inch, M1, 2020)", 2020, MacBookPro, Chip::m(1, Base), os(11, 0, 0), None),
+    model("MacBookPro18,1", "MacBook Pro (16-inch, 2021)", 2021, MacBookPro, Chip::m(1, Pro), os(12, 0, 0), None),
+    model("MacBookPro18,2", "MacBook Pro (16-inch, 2021)", 2021, MacBookPro, Chip::m(1, Max), os(12, 0, 0), None),
+    model("MacBookPro18,3", "MacBook Pro (14-inch, 2021)", 2021, MacBookPro, Chip::m(1, Pro), os(12, 0, 0), None),
+    model("MacBookPro18,4", "MacBook Pro (14-inch, 2021)", 2021, MacBookPro, Chip::m(1, Max), os(12, 0, 0), None),
+    model("Mac14,7", "MacBook Pro (13-inch, M2, 2022)", 2022, MacBookPro, Chip::m(2, Base), os(12, 4, 0), None),
+    model("Mac14,5", "MacBook Pro (14-inch, 2023)", 2023, MacBookPro, Chip::m(2, Max), os(13, 2, 0), None),
+    model("Mac14,9", "MacBook Pro (14-inch, 2023)", 2023, MacBookPro, Chip::m(2, Pro), os(13, 2, 0), None),
+    model("Mac14,6", "MacBook Pro (16-inch, 2023)", 2023, MacBookPro, Chip::m(2, Max), os(13, 2, 0), None),
+    model("Mac14,10", "MacBook Pro (16-inch, 2023)", 2023, MacBookPro, Chip::m(2, Pro), os(13, 2, 0), None),
+    model("Mac15,3", "MacBook Pro (14-inch, M3, Nov 2023)", 2023, MacBookPro, Chip::m(3, Base), os(14, 1, 0), None),
+    model("Mac15,6", "MacBook Pro (14-inch, M3 Pro or M3 Max, Nov 2023)", 2023, MacBookPro, Chip::m(3, Pro), os(14, 1, 0), None),
+    model("Mac15,7", "MacBook Pro (16-inch, Nov 2023)", 2023, MacBookPro, Chip::m(3, Pro), os(14, 1, 0), None),
+    model("Mac15,8", "MacBook Pro (14-inch, M3 Pro or M3 Max, Nov 2023)", 2023, MacBookPro, Chip::m(3, Max), os(14, 1, 0), None),
+    model("Mac15,9", "MacBook Pro (16-inch, Nov 2023)", 2023, MacBookPro, Chip::m(3, Max), os(14, 1, 0), None),
+    model("Mac16,1", "MacBook Pro (14-inch, M4, 2024)", 2024, MacBookPro, Chip::m(4, Base), os(15, 1, 0), None),
+    model("Mac16,8", "MacBook Pro (14-inch, M4 Pro or M4 Max, 2024)", 2024, MacBookPro, Chip::m(4, Pro), os(15, 1, 0), None),
+    model("Mac16,6", "MacBook Pro (14-inch, M4 Pro or M4 Max, 2024)", 2024, MacBookPro, Chip::m(4, Max), os(15, 1, 0), None),
+    model("Mac16,7", "MacBook Pro (16-inch, 2024)", 2024, MacBookPro, Chip::m(4, Pro), os(15, 1, 0), None),
+    model("Mac16,5", "MacBook Pro (16-inch, 2024)", 2024, MacBookPro, Chip::m(4, Max), os(15, 1, 0), None),
+
+    model("Macmini7,1", "Mac mini (Late 2014)", 2014, MacMini, INTEL, os(10, 10, 0), Some(os(12, 0, 0))),
+    model("Macmini8,1", "Mac mini (2018)", 2018, MacMini, INTEL, os(10, 14, 0), Some(os(15, 0, 0))),
+    model("Macmini9,1", "Mac mini (M1, 2020)", 2020, MacMini, Chip::m(1, Base), os(11, 0, 0), None),
+    model("Mac14,3", "Mac mini (2023)", 2023, MacMini, Chip::m(2, Base), os(13, 2, 0), None),
+    model("Mac14,12", "Mac mini (2023)", 2023, MacMini, Chip::m(2, Pro), os(13, 2, 0), None),
+    model("Mac16,10", "Mac mini (2024)", 2024, MacMini, Chip::m(4, Base), os(15, 1, 0), None),
+    model("Mac16,11", "Mac mini (2024)", 2024, MacMini, Chip::m(4, Pro), os(15, 1, 0), None),
+
+    model("iMac18,1", "iMac (21.5-inch, 2017)", 2017, IMac, INTEL, os(10, 12, 4), Some(os(13, 0, 0))),
+    model("iMac18,2", "iMac (Retina 4K, 21.5-inch, 2017)", 2017, IMac, INTEL, os(10, 12, 4), Some(os(13, 0, 0))),
+    model("iMac18,3", "iMac (Retina 5K, 27-inch, 2017)", 2017, IMac, INTEL, os(10, 12, 4), Some(os(13, 0, 0))),
+    model("iMac19,1", "iMac (Retina 5K, 27-inch, 2019)", 2019, IMac, INTEL, os(10, 14, 3), Some(os(15, 0, 0))),
+    model("iMac19,2", "iMac (Retina 4K, 21.5-inch, 2019)", 2019, IMac, INTEL, os(10, 14, 3), Some(os(15, 0, 0))),
+    model("iMac20,1", "iMac (Retina 5K, 27-inch, 2020)", 2020, IMac, INTEL, os(10, 15, 6), None),
+    model("iMac20,2", "iMac (Retina 5K, 27-inch, 2020)", 2020, IMac, INTEL, os(10, 15, 6), None),
+    model("iMac21,1", "iMac (24-inch, M1, 2021)", 2021, IMac, Chip::m(1, Base), os(11, 3, 0), None),
+    model("iMac21,2", "iMac (24-inch, M1, 2021)", 2021, IMac, Chip::m(1, Base), os(11, 3, 0), None),
+    model("Mac15,4", "iMac (24-inch, 2023, Two ports)", 2023, IMac, Chip::m(3, Base), os(14, 1, 0), None),
+    model("Mac15,5", "iMac (24-inch, 2023, Four ports)", 2023, IMac, Chip::m(3, Base), os(14, 1, 0), None),
+    model("Mac16,2", "iMac (24-inch, 2024, Two ports)", 2024, IMac, Chip::m(4, Base), os(15, 1, 0), None),
+    model("Mac16,3", "iMac (24-inch, 2024, Four ports)", 2024, IMac, Chip::m(4, Base), os(15, 1, 0), None),
+    model("iMacPro1,1", "iMac Pro (2017)", 2017, IMacPro, INTEL, os(10, 13, 2), Some(os(15, 0, 0))),
+
+    model("Mac13,1", "Mac Studio (2022)", 2022, MacStudio, Chip::m(1, Max), os(12, 3, 0), None),
+    model("Mac13,2", "Mac Studio (2022)", 2022, MacStudio, Chip::m(1, Ultra), os(12, 3, 0), None),
+    model("Mac14,13", "Mac Studio (2023)", 2023, MacStudio, Chip::m(2, Max), os(13, 4, 0), None),
+    model("Mac14,14", "Mac Studio (2023)", 2023, MacStudio, Chip::m(2, Ultra), os(13, 4, 0), None),
+    model("Mac16,9", "Mac Studio (2025)", 2025, MacStudio, Chip::m(4, Max), os(15, 3, 0), None),
+    model("Mac15,14", "Mac Studio (2025)", 2025, MacStudio, Chip::m(3, Ultra), os(15, 3, 0), None),
+
+    model("MacPro6,1", "Mac Pro (Late 2013)", 2013, MacPro, INTEL, os(10, 9, 0), Some(os(12, 0, 0))),
+    model("MacPro7,1", "Mac Pro (2019)", 2019, MacPro, INTEL, os(10, 15, 1), None),
+    model("Mac14,8", "Mac Pro (2023)", 2023, MacPro, Chip::m(2, Ultra), os(13, 4, 0), None),
+];
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use super::{Chip, ChipTier, MacModel, MODELS};
+    use crate::MacOsVersion;
+
+    #[test]
+    fn test_lookup() {
+        let model = MacModel::lookup("MacBookPro17,1").unwrap();
+        assert_eq!(model.name, "MacBook Pro (13-inch, M1, 2020)");
+        assert_eq!(model.year, 2020);
+        assert_eq!(model.chip.to_string(), "M1");
+        assert_eq!(
+            MacModel::lookup("Mac14,6").unwrap().chip,
+            Chip::AppleSilicon {
+                generation: 2,
+                tier: ChipTier::Max
+            }
+        );
+        assert_eq!(
+            MacModel::lookup("Mac16,2").unwrap().name,
+            "iMac (24-inch, 2024, Two ports)"
+        );
+        assert!(MacModel::lookup("MacBookPro99,1").is_none());
+    }
+
+    #[test]
+    fn test_can_run() {
+        let v = |s: &str| s.parse::<MacOsVersion>().unwrap();
+        let model = MacModel::lookup("Mac15,12").unwrap();
+        assert_eq!(model.min_os.to_string(), "14.4");
+        assert!(model.can_run(&v("14.4 (23E214)")));
+        assert!(!model.can_run(&v("14.3.1 (a)")));
+        assert!(MacModel::lookup("MacBookPro17,1")
+            .unwrap()
+            .can_run(&v("14.3.1")));
+
+        let model = MacModel::lookup("MacBookPro14,1").unwrap();
+        assert_eq!(model.max_os, Some(v("13.0")));
+        assert!(model.can_run(&v("13.7.8 (22H730)")));
+        assert!(!model.can_run(&v("14.0")));
+        let model = MacModel::lookup("MacBook8,1").unwrap();
+        assert!(model.can_run(&v("10.16")));
+        assert!(!model.can_run(&v("12.0")));
+        assert!(MacModel::lookup("MacPro7,1").unwrap().can_run(&v("26.0")));
+    }
+
+    #[test]
+    fn test_unique_identifiers() {
+        let mut seen = HashSet::new();
+        for model in MODELS {
+            assert!(seen.insert(model.identifier), "{}", model.identifier);
+        }
+    }
+}
@@ -0,0 +1,494 @@
+//! Configuration of the built-in rules.
+
+use std::collections::BTreeMap;
+#[cfg(feature = "toml")]
+use std::{fmt::Display, path::Path};
+
+use crate::{
+    Architecture, Chip, ChipGeneration, Date, MacOsVersion, Severity, SysctlRule, SystemInfo,
+    VersionRange,
+};
+
+/// Very bad machine.
+pub(crate) const PULP_MACHINE: &str = "MacBookPro16,1";
+
+/// First macOS version which is not POSIX-compliant.
+const POSIX_CUTOFF: MacOsVersion = MacOsVersion::new(14, 4, 0);
+
+/// Configuration of the built-in rules, usually loaded from a TOML file.
+///
+/// The default policy is the built-in behavior of [`check`](crate::check).
+///
+/// # Example
+///
+/// ```toml
+/// fail_at = "warning"
+///
+/// [severity]
+/// eol-os = "error"
+/// bad-mac-model = "warning"
+///
+/// [os]
+/// forbid = [">=14.4", "13.0..13.3"]
+/// allow = ["=14.4.1"]
+/// forbid_beta = true
+/// forbid_end_of_life = true
+///
+/// [models]
+/// deny = ["MacBookPro16,1", "MacBookAir9,1"]
+/// allow = ["MacBookPro16,1"]
+///
+/// [cpu]
+/// require_arch = ["arm64"]
+/// forbid_chip = ["M1"]
+///
+/// [vm]
+/// mode = "allow"
+/// exempt_hardware = true
+///
+/// [hardware]
+/// min_memory_gib = 16
+/// min_physical_cores = 8
+/// min_free_disk_gib = 50
+///
+/// [[sysctl]]
+/// id = "enough-memory"
+/// key = "hw.memsize"
+/// min = 17179869184
+///
+/// [[waivers]]
+/// rule = "bad-mac-model"
+/// serial = "C02ZK0XXMD6T"
+/// expires = "2025-06-30"
+/// justification = "Refresh scheduled for June"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default, deny_unknown_fields)
+)]
+#[non_exhaustive]
+pub struct Policy {
+    /// Lowest severity at which findings fail a check, `"error"` by default.
+    pub fail_at: Severity,
+    /// Severity overrides by [`Rule::id`](crate::Rule::id), e.g. to demote a rule to a warning.
+    pub severity: BTreeMap<String, Severity>,
+    /// Policy over `kern.osproductversion`.
+    pub os: OsPolicy,
+    /// Policy over `hw.model` identifiers.
+    pub models: ModelPolicy,
+    /// Policy over the processor.
+    pub cpu: CpuPolicy,
+    /// Minimum hardware resources.
+    pub hardware: HardwarePolicy,
+    /// Policy over virtual machines.
+    pub vm: VmPolicy,
+    /// Additional rules comparing sysctl values, see [`SysctlRule`].
+    pub sysctl: Vec<SysctlRule>,
+    /// Exemptions of specific Macs from specific rules.
+    pub waivers: Vec<Waiver>,
+}
+
+impl Policy {
+    /// Loads a policy from the TOML file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the file can't be read or is not a valid policy.
+    #[cfg(feature = "toml")]
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, PolicyError> {
+        Self::from_toml(&std::fs::read_to_string(path).map_err(PolicyError::Io)?)
+    }
+
+    /// Parses a policy from TOML.
+    ///
+    /// # Errors
+    ///
+    /// Errors if `toml` is not a valid policy.
+    #[cfg(feature = "toml")]
+    pub fn from_toml(toml: &str) -> Result<Self, PolicyError> {
+        toml::from_str(toml).map_err(PolicyError::Toml)
+    }
+}
+
+/// Policy over macOS versions.
+///
+/// A version is not POSIX-compliant if it is in a forbidden range and not in an allowed one.
+///
+/// Bounds may have a Rapid Security Response letter, e.g. `>=13.4.1 (c)`, but live Macs are
+/// compared as if they had no Rapid Security Response applied, since `kern.osproductversion`
+/// lacks the letter; see [`MacOsVersion`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default, deny_unknown_fields)
+)]
+#[non_exhaustive]
+pub struct OsPolicy {
+    /// Forbidden version ranges, `[">=14.4"]` by default.
+    pub forbid: Vec<VersionRange>,
+    /// Allowed version ranges, taking precedence over [`OsPolicy::forbid`].
+    pub allow: Vec<VersionRange>,
+    /// Whether beta and internal builds are forbidden, `false` by default.
+    pub forbid_beta: bool,
+    /// Whether releases which no longer receive security updates are forbidden, `false` by default.
+    pub forbid_end_of_life: bool,
+}
+
+impl OsPolicy {
+    /// The first forbidden range containing `version`, unless `version` is allowed.
+    pub fn forbidding(&self, version: &MacOsVersion) -> Option<&VersionRange> {
+        if self.allow.iter().any(|range| range.contains(version)) {
+            None
+        } else {
+            self.forbid.iter().find(|range| range.contains(version))
+        }
+    }
+}
+
+impl Default for OsPolicy {
+    fn default() -> Self {
+        Self {
+            forbid: vec![VersionRange::at_least(POSIX_CUTOFF)],
+            allow: Vec::new(),
+            forbid_beta: false,
+            forbid_end_of_life: false,
+        }
+    }
+}
+
+/// Policy over `hw.model` identifiers, e.g. `MacBookPro16,1`.
+///
+/// A model is bad if it is denied and not explicitly allowed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default, deny_unknown_fields)
+)]
+#[non_exhaustive]
+pub struct ModelPolicy {
+    /// Disallowed models, `["MacBookPro16,1"]` by default.
+    pub deny: Vec<String>,
+    /// Allowed models, taking precedence over [`ModelPolicy::deny`].
+    pub allow: Vec<String>,
+}
+
+impl ModelPolicy {
+    /// Whether `model` is bad under this policy.
+    pub fn is_denied(&self, model: &str) -> bool {
+        self.deny.iter().any(|denied| denied == model)
+            && !self.allow.iter().any(|allowed| allowed == model)
+    }
+}
+
+impl Default for ModelPolicy {
+    fn default() -> Self {
+        Self {
+            deny: vec![PULP_MACHINE.to_owned()],
+            allow: Vec::new(),
+        }
+    }
+}
+
+/// Policy over the architecture and Apple silicon generation of the processor.
+///
+/// A processor is allowed if its architecture is required, when any are, and not forbidden, and
+/// likewise for its chip. Intel processors match no chip generation.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default, deny_unknown_fields)
+)]
+#[non_exhaustive]
+pub struct CpuPolicy {
+    /// Allowed architectures, any if empty.
+    pub require_arch: Vec<Architecture>,
+    /// Forbidden architectures.
+    pub forbid_arch: Vec<Architecture>,
+    /// Allowed chip generations, e.g. `M2` or `M2 Pro`, any if empty.
+    pub require_chip: Vec<ChipGeneration>,
+    /// Forbidden chip generations.
+    pub forbid_chip: Vec<ChipGeneration>,
+}
+
+impl CpuPolicy {
+    /// Whether the policy constrains nothing.
+    pub fn is_empty(&self) -> bool {
+        self.require_arch.is_empty()
+            && self.forbid_arch.is_empty()
+            && self.require_chip.is_empty()
+            && self.forbid_chip.is_empty()
+    }
+
+    /// Whether a processor of `architecture` is allowed, with chip generations only checked if
+    /// `chip` is known.
+    pub fn allows(&self, architecture: Architecture, chip: Option<&Chip>) -> bool {
+        let architecture_allowed = (self.require_arch.is_empty()
+            || self.require_arch.contains(&architecture))
+            && !self.forbid_arch.contains(&architecture);
+        let chip_allowed = chip.map_or(true, |chip| {
+            (self.require_chip.is_empty() || self.require_chip.iter().any(|g| g.matches(chip)))
+                && !self.forbid_chip.iter().any(|g| g.matches(chip))
+        });
+        architecture_allowed && chip_allowed
+    }
+}
+
+/// Minimum hardware resources of the Mac, each unchecked if unset.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default, deny_unknown_fields)
+)]
+#[non_exhaustive]
+pub struct HardwarePolicy {
+    /// Minimum physical memory (`hw.memsize`) in GiB.
+    pub min_memory_gib: Option<u64>,
+    /// Minimum physical CPU cores (`hw.physicalcpu`).
+    pub min_physical_cores: Option<u32>,
+    /// Minimum logical CPU cores (`hw.logicalcpu`).
+    pub min_logical_cores: Option<u32>,
+    /// Minimum free space on the root volume in GiB.
+    pub min_free_disk_gib: Option<u64>,
+}
+
+/// Policy over virtual machines, e.g. CI runners, detected by `kern.hv_vmm_present` and virtual
+/// `hw.model` identifiers like `VirtualMac2,1`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default, deny_unknown_fields)
+)]
+#[non_exhaustive]
+pub struct VmPolicy {
+    /// Whether virtual machines are allowed, required or forbidden, `"allow"` by default.
+    pub mode: VmMode,
+    /// Whether the processor and hardware resource rules pass on virtual machines, whose virtual
+    /// hardware says little about the host. `false` by default.
+    pub exempt_hardware: bool,
+}
+
+/// Whether a [`VmPolicy`] allows, requires or forbids virtual machines.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum VmMode {
+    /// Both physical and virtual Macs pass.
+    #[default]
+    Allow,
+    /// Only virtual machines pass.
+    Require,
+    /// Only physical Macs pass.
+    Forbid,
+}
+
+/// Time-limited exemption of specific Macs from a rule.
+///
+/// A waiver matches a Mac if any of its identifiers equals the Mac's, ignoring ASCII case, so a waiver without
+/// identifiers matches nothing. It applies through its expiry date.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(deny_unknown_fields)
+)]
+#[non_exhaustive]
+pub struct Waiver {
+    /// [`Rule::id`](crate::Rule::id) of the waived rule.
+    pub rule: String,
+    /// Hostname of the Mac (`kern.hostname`).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub hostname: Option<String>,
+    /// Hardware UUID of the Mac (`IOPlatformUUID`).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub hardware_uuid: Option<String>,
+    /// Serial number of the Mac (`IOPlatformSerialNumber`).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub serial: Option<String>,
+    /// Last day the waiver applies.
+    pub expires: Date,
+    /// Why the Mac is exempt.
+    pub justification: String,
+}
+
+impl Waiver {
+    /// Creates a waiver of the rule `rule` through `expires`, without identifiers.
+    pub fn new(rule: impl Into<String>, expires: Date, justification: impl Into<String>) -> Self {
+        Self {
+            rule: rule.into(),
+            hostname: None,
+            hardware_uuid: None,
+            serial: None,
+            expires,
+            justification: justification.into(),
+        }
+    }
+
+    /// Matches Macs with the hostname `hostname`.
+    pub fn with_hostname(mut self, hostname: impl Into<String>) -> Self {
+        self.hostname = Some(hostname.into());
+        self
+    }
+
+    /// Matches Macs with the hardware UUID `uuid`.
+    pub fn with_hardware_uuid(mut self, uuid: impl Into<String>) -> Self {
+        self.hardware_uuid = Some(uuid.into());
+        self
+    }
+
+    /// Matches Macs with the serial number `serial`.
+    pub fn with_serial(mut self, serial: impl Into<String>) -> Self {
+        self.serial = Some(serial.into());
+        self
+    }
+
+    /// Whether the waiver still applies on `date`.
+    pub fn is_active(&self, date: Date) -> bool {
+        date <= self.expires
+    }
+
+    /// Whether any identifier of the waiver equals the one of `system`.
+    ///
+    /// Only the identifiers set on the waiver are read.
+    pub fn matches(&self, system: &dyn SystemInfo) -> bool {
+        let matches = |expected: &Option<String>,
+                       read: &dyn Fn() -> Result<String, crate::Error>| {
+            expected.as_ref().is_some_and(|expected| {
+                read().is_ok_and(|value| value.eq_ignore_ascii_case(expected))
+            })
+        };
+        matches(&self.hostname, &|| system.hostname())
+            || matches(&self.hardware_uuid, &|| system.hardware_uuid())
+            || matches(&self.serial, &|| system.serial_number())
+    }
+}
+
+/// Error when loading a [`Policy`].
+#[cfg(feature = "toml")]
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum PolicyError {
+    /// The policy file can't be read.
+    Io(std::io::Error),
+    /// The policy is not valid TOML or doesn't match the policy schema.
+    Toml(toml::de::Error),
+}
+
+#[cfg(feature = "toml")]
+impl Display for PolicyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PolicyError::Io(err) => write!(f, "can't read policy: {}", err),
+            PolicyError::Toml(err) => write!(f, "invalid policy: {}", err),
+        }
+    }
+}
+
+#[cfg(feature = "toml")]
+impl std::error::Error for PolicyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PolicyError::Io(err) => Some(err),
+            PolicyError::Toml(err) => Some(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ModelPolicy, OsPolicy};
+    #[cfg(feature = "toml")]
+    use super::{Policy, VmMode};
+    use crate::MacOsVersion;
+    #[cfg(feature = "toml")]
+    use crate::{Date, Severity};
+
+    #[test]
+    fn test_os_policy() {
+        let v = |s: &str| s.parse::<MacOsVersion>().unwrap();
+        let policy = OsPolicy::default();
+        assert_eq!(policy.forbidding(&v("14.4")).unwrap().to_string(), ">=14.4");
+        assert!(policy.forbidding(&v("14.3.1")).is_none());
+
+        let policy = OsPolicy {
+            forbid: vec![">=14.4".parse().unwrap(), "13.0..13.3".parse().unwrap()],
+            allow: vec!["=14.4.1".parse().unwrap()],
+            ..OsPolicy::default()
+        };
+        assert!(policy.forbidding(&v("14.4.1")).is_none());
+        assert_eq!(policy.forbidding(&v("14.5")).unwrap().to_string(), ">=14.4");
+        assert_eq!(
+            policy.forbidding(&v("13.2")).unwrap().to_string(),
+            "13.0..13.3"
+        );
+        assert!(policy.forbidding(&v("13.3")).is_none());
+    }
+
+    #[test]
+    fn test_model_policy() {
+        let policy = ModelPolicy::default();
+        assert!(policy.is_denied("MacBookPro16,1"));
+        assert!(!policy.is_denied("MacBookPro17,1"));
+
+        let policy = ModelPolicy {
+            deny: vec!["MacBookPro16,1".to_owned(), "MacBookAir9,1".to_owned()],
+            allow: vec!["MacBookPro16,1".to_owned()],
+        };
+        assert!(!policy.is_denied("MacBookPro16,1"));
+        assert!(policy.is_denied("MacBookAir9,1"));
+    }
+
+    #[test]
+    #[cfg(feature = "toml")]
+    fn test_from_toml() {
+        assert_eq!(Policy::from_toml("").unwrap(), Policy::default());
+
+        let policy = Policy::from_toml("[models]\ndeny = [\"MacBookAir9,1\"]\n").unwrap();
+        assert_eq!(policy.models.deny, ["MacBookAir9,1"]);
+        assert!(policy.models.allow.is_empty());
+
+        let policy = Policy::from_toml("[models]\nallow = [\"MacBookPro16,1\"]\n").unwrap();
+        assert_eq!(policy.models.deny, ["MacBookPro16,1"]);
+        assert!(!policy.models.is_denied("MacBookPro16,1"));
+
+        assert!(Policy::from_toml("[models]\nbanned = []\n").is_err());
+
+        let policy =
+            Policy::from_toml("fail_at = \"warning\"\n[severity]\nnot-posix = \"info\"\n").unwrap();
+        assert_eq!(policy.fail_at, Severity::Warning);
+        assert_eq!(policy.severity["not-posix"], Severity::Info);
+        assert!(Policy::from_toml("fail_at = \"fatal\"\n").is_err());
+
+        let policy = Policy::from_toml("[hardware]\nmin_memory_gib = 16\n").unwrap();
+        assert_eq!(policy.hardware.min_memory_gib, Some(16));
+        assert_eq!(policy.hardware.min_free_disk_gib, None);
+        assert!(Policy::from_toml("[hardware]\nmin_memory = \"16 GiB\"\n").is_err());
+
+        let policy = Policy::from_toml("[vm]\nmode = \"forbid\"\n").unwrap();
+        assert_eq!(policy.vm.mode, VmMode::Forbid);
+        assert!(!policy.vm.exempt_hardware);
+        assert!(Policy::from_toml("[vm]\nmode = \"maybe\"\n").is_err());
+
+        let policy = Policy::from_toml(
+            "[[waivers]]\nrule = \"bad-mac-model\"\nhostname = \"build-01\"\n\
+             expires = \"2025-06-30\"\njustification = \"Refresh in June\"\n",
+        )
+        .unwrap();
+        assert_eq!(policy.waivers[0].hostname.as_deref(), Some("build-01"));
+        assert_eq!(policy.waivers[0].expires, Date::new(2025, 6, 30));
+        assert!(Policy::from_toml("[[waivers]]\nrule = \"bad-mac-model\"\n").is_err());
+
+        let policy = Policy::from_toml("[os]\nforbid = [\">=15.0\", \"14.0..14.2\"]\n").unwrap();
+        assert_eq!(policy.os.forbid.len(), 2);
+        assert!(Policy::from_toml("[os]\nforbid = [\">=fifteen\"]\n").is_err());
+    }
+}
@@ -0,0 +1,184 @@
+//! Knowledge base of macOS releases.
+
+use std::fmt::Display;
+
+use self::SupportStatus::{Current, EndOfLife, SecurityUpdates};
+use crate::{Date, MacOsVersion};
+
+/// Whether Apple still ships updates for a macOS release.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum SupportStatus {
+    /// The latest release, receiving feature and security updates.
+    Current,
+    /// An older release still receiving security updates.
+    SecurityUpdates,
+    /// A release which no longer receives security updates.
+    EndOfLife,
+}
+
+impl SupportStatus {
+    /// Whether the release still receives security updates.
+    pub fn is_supported(&self) -> bool {
+        !matches!(self, SupportStatus::EndOfLife)
+    }
+}
+
+impl Display for SupportStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            SupportStatus::Current => "current",
+            SupportStatus::SecurityUpdates => "security updates",
+            SupportStatus::EndOfLife => "end of life",
+        })
+    }
+}
+
+/// A major macOS release, e.g. Sonoma.
+///
+/// The support status reflects Apple's practice of shipping security updates for the current
+/// release and the two before it, as of this crate's release.
+///
+/// # Example
+///
+/// ```
+/// use dikc_detector::{MacOsRelease, MacOsVersion};
+///
+/// let release = MacOsRelease::for_version(&MacOsVersion::new(14, 5, 0)).unwrap();
+/// assert_eq!(release.name, "Sonoma");
+/// assert_eq!(MacOsRelease::describe(&MacOsVersion::new(14, 5, 0)), "Sonoma 14.5");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct MacOsRelease {
+    /// Marketing name, e.g. `Sonoma`.
+    pub name: &'static str,
+    /// First version of the release, e.g. `14.0` or `10.15`.
+    pub version: MacOsVersion,
+    /// Release date of the first version.
+    pub released: Date,
+    /// Whether the release still receives updates.
+    pub support: SupportStatus,
+}
+
+impl MacOsRelease {
+    /// Looks up the release `version` belongs to.
+    pub fn for_version(version: &MacOsVersion) -> Option<&'static MacOsRelease> {
+        RELEASES.iter().find(|release| {
+            release.version.major == version.major
+                && (release.version.major != 10 || release.version.minor == version.minor)
+        })
+    }
+
+    /// Every known release, oldest first.
+    pub fn all() -> &'static [MacOsRelease] {
+        RELEASES
+    }
+
+    /// The support status of `version`.
+    ///
+    /// Unknown versions older than every known release are end of life, other unknown versions
+    /// are assumed to be current.
+    pub fn support_of(version: &MacOsVersion) -> SupportStatus {
+        match Self::for_version(version) {
+            Some(release) => release.support,
+            None if version < &RELEASES[0].version => SupportStatus::EndOfLife,
+            None => SupportStatus::Current,
+        }
+    }
+
+    /// Describes `version` with the name of its release, e.g. `Sonoma 14.5`, or just the version
+    /// if the release is unknown.
+    pub fn describe(version: &MacOsVersion) -> String {
+        match Self::for_version(version) {
+            Some(release) => format!("{} {}", release.name, version),
+            None => version.to_string(),
+        }
+    }
+}
+
+impl Display for MacOsRelease {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "macOS {} {}", self.name, self.version)
+    }
+}
+
+const fn release(
+    name: &'static str,
+    major: u32,
+    minor: u32,
+    released: Date,
+    support: SupportStatus,
+) -> MacOsRelease {
+    MacOsRelease {
+        name,
+        version: MacOsVersion::new(major, minor, 0),
+        released,
+        support,
+    }
+}
+
+#[rustfmt::skip]
+static RELEASES: &[MacOsRelease] = &[
+    release("Mavericks", 10, 9, Date::new(2013, 10, 22), EndOfLife),
+    release("Yosemite", 10, 10, Date::new(2014, 10, 16), EndOfLife),
+    release("El Capitan", 10, 11, Date::new(2015, 9, 30), EndOfLife),
+    release("Sierra", 10, 12, Date::new(2016, 9, 20), EndOfLife),
+    release("High Sierra", 10, 13, Date::new(2017, 9, 25), EndOfLife),
+    release("Mojave", 10, 14, Date::new(2018, 9, 24), EndOfLife),
+    release("Catalina", 10, 15, Date::new(2019, 10, 7), EndOfLife),
+    release("Big Sur", 11, 0, Date::new(2020, 11, 12), EndOfLife),
+    release("Monterey", 12, 0, Date::new(2021, 10, 25), EndOfLife),
+    release("Ventura", 13, 0, Date::new(2022, 10, 24), EndOfLife),
+    release("Sonoma", 14, 0, Date::new(2023, 9, 26), SecurityUpdates),
+    release("Sequoia", 15, 0, Date::new(2024, 9, 16), SecurityUpdates),
+    release("Tahoe", 26, 0, Date::new(2025, 9, 15), Current),
+];
+
+#[cfg(test)]
+mod test {
+    use super::{MacOsRelease, SupportStatus};
+    use crate::MacOsVersion;
+
+    #[test]
+    fn test_lookup() {
+        let v = |s: &str| s.parse::<MacOsVersion>().unwrap();
+        assert_eq!(
+            MacOsRelease::for_version(&v("10.15.7")).unwrap().name,
+            "Catalina"
+        );
+        assert_eq!(
+            MacOsRelease::for_version(&v("13.4.1 (a)")).unwrap().name,
+            "Ventura"
+        );
+        assert!(MacOsRelease::for_version(&v("10.16")).is_none());
+        assert_eq!(MacOsRelease::describe(&v("26.1")), "Tahoe 26.1");
+        assert_eq!(MacOsRelease::describe(&v("27.0")), "27.0");
+        assert_eq!(
+            MacOsRelease::support_of(&v("12.7.6")),
+            SupportStatus::EndOfLife
+        );
+        assert_eq!(
+            MacOsRelease::support_of(&v("10.8.5")),
+            SupportStatus::EndOfLife
+        );
+        assert_eq!(
+            MacOsRelease::support_of(&v("15.1")),
+            SupportStatus::SecurityUpdates
+        );
+        assert_eq!(MacOsRelease::support_of(&v("27.0")), SupportStatus::Current);
+    }
+
+    #[test]
+    fn test_sorted() {
+        let releases = MacOsRelease::all();
+        assert!(releases
+            .windows(2)
+            .all(|pair| pair[0].version < pair[1].version && pair[0].released < pair[1].released));
+    }
+}
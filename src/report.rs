@@ -0,0 +1,470 @@
+//! Structured results of running a [`Detector`](crate::Detector).
+
+use std::{cell::RefCell, collections::BTreeMap, fmt::Display, str::FromStr};
+
+use crate::{Error, SystemInfo, Waiver};
+
+/// Outcome of a single rule.
+#[derive(Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(tag = "status", content = "error", rename_all = "kebab-case")
+)]
+#[non_exhaustive]
+pub enum Outcome {
+    /// The system complies with the rule.
+    Pass,
+    /// The system violates the rule.
+    Fail(Error),
+    /// The rule couldn't be evaluated, e.g. because a sysctl couldn't be read.
+    Error(Error),
+    /// The system violates the rule, but is exempt by an active [`Finding::waiver`].
+    Waived(Error),
+    /// The rule is disabled, or wasn't evaluated because the platform is unsupported.
+    Skipped,
+}
+
+impl Outcome {
+    /// Classifies the result of [`Rule::evaluate`](crate::Rule::evaluate).
+    pub(crate) fn from_result(result: Result<(), Error>) -> Self {
+        match result {
+            Ok(()) => Outcome::Pass,
+            Err(err) if err.is_probe_failure() => Outcome::Error(err),
+            Err(err) => Outcome::Fail(err),
+        }
+    }
+
+    /// The error of a failed or errored rule.
+    pub fn error(&self) -> Option<&Error> {
+        match self {
+            Outcome::Fail(err) | Outcome::Error(err) => Some(err),
+            Outcome::Pass | Outcome::Waived(_) | Outcome::Skipped => None,
+        }
+    }
+}
+
+/// How bad a violation of a rule is, from [`Severity::Info`] to [`Severity::Critical`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum Severity {
+    /// Worth knowing, never fails a check by default.
+    Info,
+    /// Should be looked at, doesn't fail a check by default.
+    Warning,
+    /// Fails a check, the default of every rule.
+    #[default]
+    Error,
+    /// Must be fixed right away.
+    Critical,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+            Severity::Critical => "critical",
+        })
+    }
+}
+
+impl FromStr for Severity {
+    type Err = ParseSeverityError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "info" => Ok(Severity::Info),
+            "warning" => Ok(Severity::Warning),
+            "error" => Ok(Severity::Error),
+            "critical" => Ok(Severity::Critical),
+            _ => Err(ParseSeverityError {
+                input: s.to_owned(),
+            }),
+        }
+    }
+}
+
+/// Error when parsing a [`Severity`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseSeverityError {
+    input: String,
+}
+
+impl Display for ParseSeverityError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "invalid severity `{}`, expected info, warning, error or critical",
+            self.input
+        )
+    }
+}
+
+impl std::error::Error for ParseSeverityError {}
+
+/// Result of one rule in a [`Report`].
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct Finding {
+    /// [`Rule::id`](crate::Rule::id) of the rule.
+    pub rule_id: String,
+    /// [`Rule::description`](crate::Rule::description) of the rule.
+    pub description: String,
+    /// Severity of the rule, see [`Detector::severity`](crate::Detector::severity).
+    pub severity: Severity,
+    /// What happened when the rule ran.
+    pub outcome: Outcome,
+    /// System information values the rule read, keyed by sysctl name.
+    pub observed: BTreeMap<String, String>,
+    /// Facts derived by the rule, see [`Rule::details`](crate::Rule::details).
+    pub details: BTreeMap<String, String>,
+    /// The waiver exempting the system from the rule, if the outcome is [`Outcome::Waived`].
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub waiver: Option<Waiver>,
+}
+
+impl Finding {
+    pub(crate) fn new(
+        rule_id: impl Into<String>,
+        description: impl Into<String>,
+        severity: Severity,
+        outcome: Outcome,
+    ) -> Self {
+        Self {
+            rule_id: rule_id.into(),
+            description: description.into(),
+            severity,
+            outcome,
+            observed: BTreeMap::new(),
+            details: BTreeMap::new(),
+            waiver: None,
+        }
+    }
+}
+
+/// Summary verdict of a [`Report`], where waived findings count as passing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum Verdict {
+    /// Every rule that ran passed.
+    Pass,
+    /// At least one rule failed.
+    Fail,
+    /// No rule failed, but at least one couldn't be evaluated.
+    Error,
+}
+
+impl Display for Verdict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Verdict::Pass => "pass",
+            Verdict::Fail => "fail",
+            Verdict::Error => "error",
+        })
+    }
+}
+
+/// Every finding of a [`Detector`](crate::Detector) run.
+#[derive(Debug, Default)]
+pub struct Report {
+    /// Findings in rule registration order, including skipped rules.
+    pub findings: Vec<Finding>,
+    /// Lowest severity at which findings fail, see
+    /// [`Detector::threshold`](crate::Detector::threshold). The serialized verdict is
+    /// [`Report::verdict_at`] this threshold.
+    pub threshold: Severity,
+}
+
+impl Report {
+    /// Summary verdict over all findings, regardless of their severity.
+    pub fn verdict(&self) -> Verdict {
+        self.verdict_at(Severity::Info)
+    }
+
+    /// Summary verdict over the findings at or above `threshold`.
+    pub fn verdict_at(&self, threshold: Severity) -> Verdict {
+        let outcomes = || {
+            self.findings
+                .iter()
+                .filter(move |finding| finding.severity >= threshold)
+                .map(|finding| &finding.outcome)
+        };
+        if outcomes().any(|outcome| matches!(outcome, Outcome::Fail(_))) {
+            Verdict::Fail
+        } else if outcomes().any(|outcome| matches!(outcome, Outcome::Error(_))) {
+            Verdict::Error
+        } else {
+            Verdict::Pass
+        }
+    }
+
+    /// Every value observed by any rule, keyed by sysctl name.
+    pub fn observed(&self) -> BTreeMap<&str, &str> {
+        self.findings
+            .iter()
+            .flat_map(|finding| &finding.observed)
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect()
+    }
+
+    /// Collapses the report into the result returned by [`check`](crate::check), ignoring
+    /// findings below [`Severity::Error`].
+    ///
+    /// # Errors
+    ///
+    /// Errors with the error of the failed or errored rule, or [`Error::Many`] if there is more than one.
+    pub fn into_result(self) -> Result<(), Error> {
+        self.into_result_at(Severity::default())
+    }
+
+    /// Collapses the report into a single result, ignoring findings below `threshold`.
+    ///
+    /// # Errors
+    ///
+    /// Errors with the error of the failed or errored rule at or above `threshold`, or
+    /// [`Error::Many`] if there is more than one. Errors with a single
+    /// [`Error::UnsupportedPlatform`] if any rule couldn't read system information on this
+    /// platform.
+    pub fn into_result_at(self, threshold: Severity) -> Result<(), Error> {
+        let mut errs: Vec<Error> = self
+            .findings
+            .into_iter()
+            .filter(|finding| finding.severity >= threshold)
+            .filter_map(|finding| match finding.outcome {
+                Outcome::Fail(err) | Outcome::Error(err) => Some(err),
+                _ => None,
+            })
+            .collect();
+        if errs
+            .iter()
+            .any(|err| matches!(err, Error::UnsupportedPlatform))
+        {
+            return Err(Error::UnsupportedPlatform);
+        }
+        match errs.len() {
+            0 => Ok(()),
+            1 => Err(errs.remove(0)),
+            _ => Err(Error::Many(errs)),
+        }
+    }
+}
+
+/// Serializes as `{"verdict": ..., "findings": [...]}`, with the verdict at [`Report::threshold`].
+#[cfg(feature = "serde")]
+impl serde::Serialize for Report {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("Report", 2)?;
+        state.serialize_field("verdict", &self.verdict_at(self.threshold))?;
+        state.serialize_field("findings", &self.findings)?;
+        state.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Report {
+    /// Serializes the report as pretty-printed JSON.
+    ///
+    /// # Errors
+    ///
+    /// Errors if serialization fails.
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+/// [`SystemInfo`] wrapper remembering every value that was successfully read.
+pub(crate) struct Recorder<'a> {
+    system: &'a dyn SystemInfo,
+    observed: RefCell<BTreeMap<String, String>>,
+}
+
+impl<'a> Recorder<'a> {
+    pub(crate) fn new(system: &'a dyn SystemInfo) -> Self {
+        Self {
+            system,
+            observed: RefCell::new(BTreeMap::new()),
+        }
+    }
+
+    pub(crate) fn into_observed(self) -> BTreeMap<String, String> {
+        self.observed.into_inner()
+    }
+}
+
+impl SystemInfo for Recorder<'_> {
+    fn value_string(&self, name: &str) -> Result<String, Error> {
+        let value = self.system.value_string(name)?;
+        self.observed
+            .borrow_mut()
+            .insert(name.to_owned(), value.clone());
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Severity;
+    use crate::{
+        Date, Detector, EndOfLifeRule, FakeSystem, MacModelRule, Outcome, Policy, PosixRule,
+        Verdict, Waiver, IOPLATFORM_SERIAL_NUMBER,
+    };
+
+    #[test]
+    fn test_report() {
+        let system = FakeSystem::new()
+            .with_os_product_version("14.4.1")
+            .with_kernel_release("23.4.0")
+            .with_hw_model("MacBookPro17,1");
+        let report = Detector::new().report(&system);
+        assert_eq!(report.verdict(), Verdict::Fail);
+        assert_eq!(report.findings[0].rule_id, PosixRule::ID);
+        assert!(matches!(report.findings[0].outcome, Outcome::Fail(_)));
+        assert_eq!(report.findings[1].rule_id, MacModelRule::ID);
+        assert!(matches!(report.findings[1].outcome, Outcome::Pass));
+        assert_eq!(report.observed()["kern.osproductversion"], "14.4.1");
+        assert_eq!(report.observed()["hw.model"], "MacBookPro17,1");
+        assert_eq!(
+            report.findings[1].details["model.name"],
+            "MacBook Pro (13-inch, M1, 2020)"
+        );
+
+        let report = Detector::new().disable(PosixRule::ID).report(&system);
+        assert_eq!(report.verdict(), Verdict::Pass);
+        assert!(matches!(report.findings[0].outcome, Outcome::Skipped));
+
+        let report = Detector::new().report(&FakeSystem::new().with_hw_model("Mac14,7"));
+        assert_eq!(report.verdict(), Verdict::Error);
+    }
+
+    #[test]
+    fn test_verdict_at() {
+        let mut policy = Policy::default();
+        policy.os.forbid_end_of_life = true;
+        let detector = Detector::with_policy(&policy);
+        let system = FakeSystem::new()
+            .with_os_product_version("12.7.6")
+            .with_kernel_release("21.6.0")
+            .with_hw_model("MacBookPro17,1");
+        let report = detector.report(&system);
+        assert!(matches!(
+            report
+                .findings
+                .iter()
+                .find(|f| f.rule_id == EndOfLifeRule::ID)
+                .unwrap()
+                .outcome,
+            Outcome::Fail(_)
+        ));
+        assert_eq!(report.verdict(), Verdict::Fail);
+        assert_eq!(report.verdict_at(detector.threshold()), Verdict::Pass);
+        assert_eq!(report.verdict_at(Severity::Warning), Verdict::Fail);
+        assert!(detector.run(&system).is_ok());
+        #[cfg(feature = "serde")]
+        assert!(report.to_json().unwrap().contains("\"verdict\": \"pass\""));
+    }
+
+    #[test]
+    fn test_severity() {
+        let system = FakeSystem::new()
+            .with_os_product_version("14.4.1")
+            .with_kernel_release("23.4.0")
+            .with_hw_model("MacBookPro16,1");
+        let report = Detector::new().report(&system);
+        assert_eq!(report.findings[0].severity, Severity::Error);
+        assert_eq!(report.findings[1].severity, Severity::Critical);
+        assert!(matches!(
+            report.into_result_at(Severity::Critical),
+            Err(err) if err.id() == MacModelRule::ID
+        ));
+
+        let detector = Detector::new().severity(PosixRule::ID, Severity::Warning);
+        assert_eq!(
+            detector.report(&system).findings[0].severity,
+            Severity::Warning
+        );
+        assert!(matches!(detector.run(&system), Err(err) if err.id() == MacModelRule::ID));
+        let detector = detector.fail_at(Severity::Warning);
+        assert!(matches!(detector.run(&system), Err(crate::Error::Many(_))));
+
+        assert_eq!("warning".parse::<Severity>().unwrap(), Severity::Warning);
+        assert!("fatal".parse::<Severity>().is_err());
+    }
+
+    #[test]
+    fn test_waiver() {
+        let system = FakeSystem::new()
+            .with_os_product_version("14.3.1")
+            .with_kernel_release("23.3.0")
+            .with_hw_model("MacBookPro16,1")
+            .with_value(IOPLATFORM_SERIAL_NUMBER, "C02ZK0XXMD6T");
+        let waiver = Waiver::new(MacModelRule::ID, Date::new(2025, 6, 30), "Refresh in June")
+            .with_hostname("build-01")
+            .with_serial("c02zk0xxmd6t");
+        let detector = Detector::new().waiver(waiver);
+
+        let report = detector.date(Date::new(2025, 6, 30)).report(&system);
+        assert_eq!(report.verdict(), Verdict::Pass);
+        assert!(matches!(report.findings[1].outcome, Outcome::Waived(_)));
+        assert_eq!(
+            report.findings[1].waiver.as_ref().unwrap().justification,
+            "Refresh in June"
+        );
+        assert_eq!(
+            report.findings[1].observed[IOPLATFORM_SERIAL_NUMBER],
+            "C02ZK0XXMD6T"
+        );
+
+        let detector = Detector::new()
+            .waiver(
+                Waiver::new(MacModelRule::ID, Date::new(2025, 6, 30), "")
+                    .with_serial("C02ZK0XXMD6T"),
+            )
+            .date(Date::new(2025, 7, 1));
+        let report = detector.report(&system);
+        assert!(matches!(report.findings[1].outcome, Outcome::Fail(_)));
+        assert_eq!(report.findings[1].details["waiver.expired"], "2025-06-30");
+
+        let other = system.with_value(IOPLATFORM_SERIAL_NUMBER, "C02AAAAAAAAA");
+        let detector = detector.date(Date::new(2025, 1, 1));
+        assert!(matches!(
+            detector.report(&other).findings[1].outcome,
+            Outcome::Fail(_)
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_json() {
+        let system = FakeSystem::new()
+            .with_os_product_version("14.4.1")
+            .with_kernel_release("23.4.0")
+            .with_hw_model("MacBookPro16,1");
+        let json: serde_json::Value =
+            serde_json::from_str(&Detector::new().report(&system).to_json().unwrap()).unwrap();
+        assert_eq!(json["verdict"], "fail");
+        assert_eq!(json["findings"][0]["rule_id"], "not-posix");
+        assert_eq!(json["findings"][0]["outcome"]["status"], "fail");
+        assert_eq!(json["findings"][0]["outcome"]["error"]["id"], "not-posix");
+        assert_eq!(
+            json["findings"][1]["outcome"]["error"]["id"],
+            "bad-mac-model"
+        );
+        assert_eq!(
+            json["findings"][1]["observed"]["hw.model"],
+            "MacBookPro16,1"
+        );
+    }
+}
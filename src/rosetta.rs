@@ -0,0 +1,180 @@
+//! Detection of processes translated by Rosetta.
+
+use std::collections::BTreeMap;
+
+use crate::{
+    Chip, Error, MacModel, Rule, Severity, SystemInfo, HW_OPTIONAL_ARM64, MACHDEP_CPU_BRAND_STRING,
+    SYSCTL_PROC_TRANSLATED,
+};
+
+/// Whether the process reading `system` is translated by Rosetta (`sysctl.proc_translated`).
+///
+/// Intel Macs lack the key, so a missing value means the process runs natively.
+///
+/// # Errors
+///
+/// Errors if the value can't be read for another reason.
+pub fn is_translated(system: &dyn SystemInfo) -> Result<bool, Error> {
+    match system.value_string(SYSCTL_PROC_TRANSLATED) {
+        Ok(value) => Ok(value.trim() == "1"),
+        Err(Error::Missing(_)) => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+/// View of [`SystemInfo`] with the values of the native host rather than the ones Rosetta
+/// presents to translated processes.
+///
+/// Under translation, [`HW_OPTIONAL_ARM64`] is always `1` and [`MACHDEP_CPU_BRAND_STRING`] is
+/// derived from the chip of the `hw.model`, since Rosetta reports a virtual processor; it is
+/// [`Error::Missing`] if the model is unknown, as the native chip can't be read. Every other
+/// value, and every value of native processes, is read as is; Rosetta doesn't translate memory
+/// and core counts.
+///
+/// # Example
+///
+/// ```
+/// use dikc_detector::{FakeSystem, NativeSystem, SystemInfo};
+///
+/// let system = FakeSystem::new()
+///     .with_value("sysctl.proc_translated", "1")
+///     .with_value("machdep.cpu.brand_string", "VirtualApple @ 2.50GHz processor")
+///     .with_hw_model("MacBookPro17,1");
+/// let native = NativeSystem::new(&system);
+/// assert_eq!(native.value_string("machdep.cpu.brand_string").unwrap(), "Apple M1");
+/// ```
+#[derive(Clone, Copy)]
+pub struct NativeSystem<'a> {
+    system: &'a dyn SystemInfo,
+}
+
+impl<'a> NativeSystem<'a> {
+    /// Creates the native view of `system`.
+    pub fn new(system: &'a dyn SystemInfo) -> Self {
+        Self { system }
+    }
+}
+
+impl SystemInfo for NativeSystem<'_> {
+    fn value_string(&self, name: &str) -> Result<String, Error> {
+        if !matches!(name, HW_OPTIONAL_ARM64 | MACHDEP_CPU_BRAND_STRING)
+            || !is_translated(self.system)?
+        {
+            return self.system.value_string(name);
+        }
+        if name == HW_OPTIONAL_ARM64 {
+            return Ok("1".to_owned());
+        }
+        let model = self.system.hw_model()?;
+        match MacModel::lookup(&model).map(|model| model.chip) {
+            Some(chip @ Chip::AppleSilicon { .. }) => Ok(format!("Apple {}", chip)),
+            _ => Err(Error::Missing(name.to_owned())),
+        }
+    }
+}
+
+impl std::fmt::Debug for NativeSystem<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NativeSystem").finish_non_exhaustive()
+    }
+}
+
+/// Checks whether the process runs natively rather than translated by Rosetta, in which case
+/// some values describe a virtual Intel processor.
+///
+/// Only informational by default, raise its severity to fail on translated processes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RosettaRule;
+
+impl RosettaRule {
+    /// Identifier of this rule.
+    pub const ID: &'static str = "rosetta";
+}
+
+impl Rule for RosettaRule {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+
+    fn description(&self) -> &str {
+        "Process runs natively, not translated by Rosetta"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Info
+    }
+
+    fn evaluate(&self, system: &dyn SystemInfo) -> Result<(), Error> {
+        if is_translated(system)? {
+            Err(Error::Translated)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn details(&self, system: &dyn SystemInfo) -> BTreeMap<String, String> {
+        is_translated(system)
+            .map(|translated| ("process.translated".to_owned(), translated.to_string()))
+            .into_iter()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{is_translated, NativeSystem, RosettaRule};
+    use crate::{
+        CpuPolicy, CpuRule, Detector, Error, FakeSystem, Outcome, Rule, SystemInfo,
+        HW_OPTIONAL_ARM64, MACHDEP_CPU_BRAND_STRING, SYSCTL_PROC_TRANSLATED,
+    };
+
+    #[test]
+    fn test_rosetta() {
+        let native = FakeSystem::new()
+            .with_value(SYSCTL_PROC_TRANSLATED, "0")
+            .with_value(MACHDEP_CPU_BRAND_STRING, "Apple M2 Pro")
+            .with_hw_model("Mac14,9");
+        assert!(!is_translated(&native).unwrap());
+        assert!(!is_translated(&FakeSystem::new()).unwrap());
+        assert!(RosettaRule.evaluate(&native).is_ok());
+        assert_eq!(
+            NativeSystem::new(&native)
+                .value_string(MACHDEP_CPU_BRAND_STRING)
+                .unwrap(),
+            "Apple M2 Pro"
+        );
+
+        let translated = native
+            .with_value(SYSCTL_PROC_TRANSLATED, "1")
+            .with_value(MACHDEP_CPU_BRAND_STRING, "VirtualApple @ 2.50GHz processor");
+        assert!(matches!(
+            RosettaRule.evaluate(&translated),
+            Err(Error::Translated)
+        ));
+        assert_eq!(
+            RosettaRule.details(&translated)["process.translated"],
+            "true"
+        );
+        let view = NativeSystem::new(&translated);
+        assert_eq!(view.value_string(HW_OPTIONAL_ARM64).unwrap(), "1");
+        assert_eq!(
+            view.value_string(MACHDEP_CPU_BRAND_STRING).unwrap(),
+            "Apple M2 Pro"
+        );
+
+        let unknown = translated.with_hw_model("Mac99,1");
+        assert!(matches!(
+            NativeSystem::new(&unknown).value_string(MACHDEP_CPU_BRAND_STRING),
+            Err(Error::Missing(name)) if name == MACHDEP_CPU_BRAND_STRING
+        ));
+        let rule = CpuRule::new(CpuPolicy {
+            forbid_chip: vec!["M1".parse().unwrap()],
+            ..CpuPolicy::default()
+        });
+        let report = Detector::empty().rule(rule).report(&unknown);
+        assert!(matches!(
+            &report.findings[0].outcome,
+            Outcome::Error(err) if err.is_probe_failure()
+        ));
+    }
+}
@@ -0,0 +1,479 @@
+//! Checks that can be registered with a [`Detector`](crate::Detector).
+
+use std::{collections::BTreeMap, ops::Bound};
+
+use crate::{
+    BuildKind, Error, MacModel, MacOsRelease, MacOsVersion, ModelPolicy, OsPolicy,
+    ParseVersionError, Severity, SystemInfo, VersionRange,
+};
+
+/// A single check evaluated against [`SystemInfo`].
+///
+/// # Example
+///
+/// ```
+/// use dikc_detector::{Detector, Error, FakeSystem, Rule, SystemInfo};
+///
+/// struct NoMacPro;
+///
+/// impl Rule for NoMacPro {
+///     fn id(&self) -> &str {
+///         "no-mac-pro"
+///     }
+///
+///     fn description(&self) -> &str {
+///         "The Mac is not a Mac Pro"
+///     }
+///
+///     fn evaluate(&self, system: &dyn SystemInfo) -> Result<(), Error> {
+///         if system.hw_model()?.starts_with("MacPro") {
+///             Err(Error::Custom("Mac Pros are too expensive".into()))
+///         } else {
+///             Ok(())
+///         }
+///     }
+/// }
+///
+/// let detector = Detector::new().rule(NoMacPro);
+/// let system = FakeSystem::new()
+///     .with_os_product_version("14.3.1")
+///     .with_kernel_release("23.3.0")
+///     .with_hw_model("MacPro7,1");
+/// assert!(matches!(detector.run(&system), Err(Error::Custom(_))));
+/// ```
+pub trait Rule {
+    /// Stable identifier of the rule, e.g. `not-posix`.
+    fn id(&self) -> &str;
+
+    /// Human-readable description of what the rule checks.
+    fn description(&self) -> &str;
+
+    /// How bad a violation of the rule is, [`Severity::Error`] by default.
+    ///
+    /// Can be overridden per detector with [`Detector::severity`](crate::Detector::severity).
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    /// Evaluates the rule against `system`.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the system violates the rule or the system information can't be read.
+    fn evaluate(&self, system: &dyn SystemInfo) -> Result<(), Error>;
+
+    /// Facts derived from `system` which are reported alongside the outcome, e.g. the marketing
+    /// name of the Mac model.
+    ///
+    /// Defaults to none. Values that can't be read are left out.
+    fn details(&self, system: &dyn SystemInfo) -> BTreeMap<String, String> {
+        let _ = system;
+        BTreeMap::new()
+    }
+}
+
+/// Checks whether the macOS version is forbidden by an [`OsPolicy`], by default equal to or newer
+/// than __`14.4`__, which is not POSIX-compliant.
+///
+/// Fails with [`Error::DowngradeImpossible`] instead of [`Error::NotPosix`] if the `hw.model`
+/// shipped with a version too new to downgrade out of the forbidden range.
+#[derive(Debug, Clone, Default)]
+pub struct PosixRule {
+    policy: OsPolicy,
+}
+
+impl PosixRule {
+    /// Identifier of this rule.
+    pub const ID: &'static str = "not-posix";
+
+    /// Creates the rule enforcing `policy`.
+    pub fn new(policy: OsPolicy) -> Self {
+        Self { policy }
+    }
+}
+
+impl Rule for PosixRule {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+
+    fn description(&self) -> &str {
+        "macOS version is not forbidden by the policy"
+    }
+
+    fn evaluate(&self, system: &dyn SystemInfo) -> Result<(), Error> {
+        let version = os_version(system)?;
+        let Some(range) = self.policy.forbidding(&version) else {
+            return Ok(());
+        };
+        let range = Box::new(range.clone());
+        let model = system
+            .hw_model()
+            .ok()
+            .and_then(|model| MacModel::lookup(&model));
+        match model {
+            Some(model) if !can_downgrade(model, &range) => Err(Error::DowngradeImpossible {
+                version,
+                range,
+                model,
+            }),
+            _ => Err(Error::NotPosix { version, range }),
+        }
+    }
+
+    fn details(&self, system: &dyn SystemInfo) -> BTreeMap<String, String> {
+        let mut details = BTreeMap::new();
+        if let Ok(version) = os_version(system) {
+            if let Some(release) = MacOsRelease::for_version(&version) {
+                details.insert("os.release".to_owned(), release.name.to_owned());
+            }
+            if let Some(rsr) = version.rsr {
+                details.insert("os.rsr".to_owned(), rsr.to_string());
+            }
+            if let Some(build) = version.build {
+                details.insert("os.build".to_owned(), build);
+            }
+        }
+        details
+    }
+}
+
+/// Whether `model` can run a version prior to `range`, as recommended by [`Error::NotPosix`].
+///
+/// Ranges without a lower bound recommend upgrading, which is always possible.
+fn can_downgrade(model: &MacModel, range: &VersionRange) -> bool {
+    match range.start() {
+        Bound::Included(start) => model.min_os < *start,
+        Bound::Excluded(start) => model.min_os <= *start,
+        Bound::Unbounded => true,
+    }
+}
+
+/// Reads `kern.osproductversion`, completed with the build identifier from `kern.osversion`.
+fn os_version(system: &dyn SystemInfo) -> Result<MacOsVersion, Error> {
+    let mut version: MacOsVersion = system.os_product_version()?.parse()?;
+    if version.build.is_none() {
+        version.build = system.os_build().ok();
+    }
+    Ok(version)
+}
+
+/// Checks whether the Mac model is denied by a [`ModelPolicy`], by default `MacBookPro16,1`.
+#[derive(Debug, Clone, Default)]
+pub struct MacModelRule {
+    policy: ModelPolicy,
+}
+
+impl MacModelRule {
+    /// Identifier of this rule.
+    pub const ID: &'static str = "bad-mac-model";
+
+    /// Creates the rule enforcing `policy`.
+    pub fn new(policy: ModelPolicy) -> Self {
+        Self { policy }
+    }
+}
+
+impl Rule for MacModelRule {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+
+    fn description(&self) -> &str {
+        "Mac model is not denied by the policy"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Critical
+    }
+
+    fn evaluate(&self, system: &dyn SystemInfo) -> Result<(), Error> {
+        let model = system.hw_model()?;
+        if self.policy.is_denied(&model) {
+            Err(Error::BadMacModel { model })
+        } else {
+            Ok(())
+        }
+    }
+
+    fn details(&self, system: &dyn SystemInfo) -> BTreeMap<String, String> {
+        let Some(model) = system.hw_model().ok().and_then(|id| MacModel::lookup(&id)) else {
+            return BTreeMap::new();
+        };
+        BTreeMap::from([
+            ("model.name".to_owned(), model.name.to_owned()),
+            ("model.year".to_owned(), model.year.to_string()),
+            ("model.family".to_owned(), model.family.to_string()),
+            ("model.chip".to_owned(), model.chip.to_string()),
+            ("model.min-os".to_owned(), model.min_os.to_string()),
+        ])
+    }
+}
+
+/// Checks whether `kern.osproductversion` agrees with the Darwin kernel release
+/// (`kern.osrelease`) and build version (`kern.osversion`).
+///
+/// Processes running with `SYSTEM_VERSION_COMPAT=1` or linked against old SDKs see `10.16`
+/// instead of the real version, which would otherwise pass every version rule.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VersionCompatRule;
+
+impl VersionCompatRule {
+    /// Identifier of this rule.
+    pub const ID: &'static str = "version-spoofed";
+}
+
+impl Rule for VersionCompatRule {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+
+    fn description(&self) -> &str {
+        "macOS version agrees with the Darwin kernel"
+    }
+
+    fn evaluate(&self, system: &dyn SystemInfo) -> Result<(), Error> {
+        let reported = system.os_product_version()?;
+        let version: MacOsVersion = reported.parse()?;
+        let kernel_release = system.kernel_release()?;
+        let kernel_major = leading_number(&kernel_release);
+        // The build version is missing from systems we don't control, e.g. simulated ones.
+        let build = system.os_build().ok();
+        let build_major = build.as_deref().map(leading_number);
+
+        let expected = version.darwin_major();
+        if kernel_major.is_some()
+            && expected == kernel_major
+            && build_major.map_or(true, |major| major == kernel_major)
+        {
+            Ok(())
+        } else {
+            Err(Error::VersionSpoofed {
+                reported,
+                kernel_release,
+                build,
+            })
+        }
+    }
+}
+
+/// Classifies the macOS build (`kern.osversion`) as release, beta or internal, and checks whether
+/// beta and internal builds are forbidden by [`OsPolicy::forbid_beta`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BetaRule {
+    forbid: bool,
+}
+
+impl BetaRule {
+    /// Identifier of this rule.
+    pub const ID: &'static str = "beta-os";
+
+    /// Creates the rule, failing on beta and internal builds if `forbid` is set.
+    pub fn new(forbid: bool) -> Self {
+        Self { forbid }
+    }
+}
+
+impl Rule for BetaRule {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+
+    fn description(&self) -> &str {
+        "macOS build is not a beta or internal build"
+    }
+
+    fn evaluate(&self, system: &dyn SystemInfo) -> Result<(), Error> {
+        if !self.forbid {
+            return Ok(());
+        }
+        let build = system.os_build()?;
+        match build_kind(&build)? {
+            BuildKind::Release => Ok(()),
+            kind => Err(Error::BetaOs { build, kind }),
+        }
+    }
+
+    fn details(&self, system: &dyn SystemInfo) -> BTreeMap<String, String> {
+        let kind = system
+            .os_build()
+            .ok()
+            .and_then(|build| build_kind(&build).ok());
+        kind.map(|kind| ("os.build-kind".to_owned(), kind.to_string()))
+            .into_iter()
+            .collect()
+    }
+}
+
+fn build_kind(build: &str) -> Result<BuildKind, Error> {
+    BuildKind::classify(build).ok_or_else(|| {
+        Error::ParseOsVersion(ParseVersionError {
+            input: build.to_owned(),
+        })
+    })
+}
+
+/// Checks whether the macOS release still receives security updates, failing only if
+/// [`OsPolicy::forbid_end_of_life`] is set.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EndOfLifeRule {
+    forbid: bool,
+}
+
+impl EndOfLifeRule {
+    /// Identifier of this rule.
+    pub const ID: &'static str = "eol-os";
+
+    /// Creates the rule, failing on end-of-life releases if `forbid` is set.
+    pub fn new(forbid: bool) -> Self {
+        Self { forbid }
+    }
+}
+
+impl Rule for EndOfLifeRule {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+
+    fn description(&self) -> &str {
+        "macOS release still receives security updates"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn evaluate(&self, system: &dyn SystemInfo) -> Result<(), Error> {
+        if !self.forbid {
+            return Ok(());
+        }
+        let version: MacOsVersion = system.os_product_version()?.parse()?;
+        if MacOsRelease::support_of(&version).is_supported() {
+            Ok(())
+        } else {
+            Err(Error::EndOfLife { version })
+        }
+    }
+
+    fn details(&self, system: &dyn SystemInfo) -> BTreeMap<String, String> {
+        let Some(version) = system
+            .os_product_version()
+            .ok()
+            .and_then(|version| version.parse::<MacOsVersion>().ok())
+        else {
+            return BTreeMap::new();
+        };
+        let mut details = BTreeMap::from([(
+            "os.support".to_owned(),
+            MacOsRelease::support_of(&version).to_string(),
+        )]);
+        if let Some(release) = MacOsRelease::for_version(&version) {
+            details.insert("os.release".to_owned(), release.name.to_owned());
+            details.insert("os.released".to_owned(), release.released.to_string());
+        }
+        details
+    }
+}
+
+/// Parses the leading digits of `s`, e.g. `23` of `23.4.0` or `23E214`.
+fn leading_number(s: &str) -> Option<u32> {
+    let end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    s[..end].parse().ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BetaRule, EndOfLifeRule, PosixRule, Rule, VersionCompatRule};
+    use crate::{BuildKind, Error, FakeSystem};
+
+    #[test]
+    fn test_beta() {
+        let beta = FakeSystem::new().with_os_build("24A5264n");
+        assert!(BetaRule::new(false).evaluate(&beta).is_ok());
+        assert_eq!(BetaRule::new(false).details(&beta)["os.build-kind"], "beta");
+        assert!(matches!(
+            BetaRule::new(true).evaluate(&beta),
+            Err(Error::BetaOs {
+                kind: BuildKind::Beta,
+                ..
+            })
+        ));
+        assert!(BetaRule::new(true)
+            .evaluate(&FakeSystem::new().with_os_build("24A335"))
+            .is_ok());
+        assert!(BetaRule::new(false).evaluate(&FakeSystem::new()).is_ok());
+        assert!(BetaRule::new(true).evaluate(&FakeSystem::new()).is_err());
+    }
+
+    #[test]
+    fn test_downgrade() {
+        let rule = PosixRule::default();
+        let m3 = FakeSystem::new()
+            .with_os_product_version("14.5")
+            .with_hw_model("Mac15,12");
+        assert!(matches!(
+            rule.evaluate(&m3),
+            Err(Error::DowngradeImpossible { model, .. }) if model.identifier == "Mac15,12"
+        ));
+
+        let m1 = m3.clone().with_hw_model("MacBookPro17,1");
+        assert!(matches!(rule.evaluate(&m1), Err(Error::NotPosix { .. })));
+        let unknown = m3.with_hw_model("Mac99,1");
+        assert!(matches!(
+            rule.evaluate(&unknown),
+            Err(Error::NotPosix { .. })
+        ));
+    }
+
+    #[test]
+    fn test_end_of_life() {
+        let ventura = FakeSystem::new().with_os_product_version("13.7.8");
+        assert!(EndOfLifeRule::new(false).evaluate(&ventura).is_ok());
+        assert!(matches!(
+            EndOfLifeRule::new(true).evaluate(&ventura),
+            Err(Error::EndOfLife { .. })
+        ));
+        let details = EndOfLifeRule::new(false).details(&ventura);
+        assert_eq!(details["os.release"], "Ventura");
+        assert_eq!(details["os.support"], "end of life");
+
+        let sequoia = FakeSystem::new().with_os_product_version("15.7");
+        assert!(EndOfLifeRule::new(true).evaluate(&sequoia).is_ok());
+    }
+
+    #[test]
+    fn test_version_compat() {
+        let system = |version: &str, release: &str, build: &str| {
+            let system = FakeSystem::new()
+                .with_os_product_version(version)
+                .with_kernel_release(release);
+            VersionCompatRule.evaluate(&if build.is_empty() {
+                system
+            } else {
+                system.with_os_build(build)
+            })
+        };
+        assert!(system("14.4.1", "23.4.0", "23E224").is_ok());
+        assert!(system("15.1", "24.1.0", "").is_ok());
+        assert!(system("26.0", "25.0.0", "25A354").is_ok());
+        assert!(system("10.15.7", "19.6.0", "19H2").is_ok());
+        for (version, release, build) in [
+            ("10.16", "23.4.0", "23E224"),
+            ("10.16", "20.6.0", "20G165"),
+            ("13.6", "23.4.0", "23E224"),
+            ("14.4.1", "23.4.0", "22G120"),
+            ("14.4.1", "Darwin", ""),
+        ] {
+            assert!(
+                matches!(
+                    system(version, release, build),
+                    Err(Error::VersionSpoofed { .. })
+                ),
+                "{} {} {}",
+                version,
+                release,
+                build
+            );
+        }
+    }
+}
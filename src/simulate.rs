@@ -0,0 +1,351 @@
+//! What-if simulations of OS upgrades and hardware swaps.
+
+use std::{collections::BTreeMap, fmt::Display};
+
+use crate::{
+    Chip, Error, Finding, MacModel, MacOsVersion, Report, SystemInfo, HW_MODEL, HW_OPTIONAL_ARM64,
+    KERN_OSPRODUCTVERSION, KERN_OSRELEASE, KERN_OSVERSION, MACHDEP_CPU_BRAND_STRING,
+    SYSCTL_PROC_TRANSLATED,
+};
+
+/// Overrides of system information values, e.g. "what if this Mac ran macOS 15.1".
+///
+/// # Example
+///
+/// ```
+/// use dikc_detector::{Detector, FakeSystem, MacOsVersion, WhatIf};
+///
+/// let system = FakeSystem::new()
+///     .with_os_product_version("14.3.1")
+///     .with_kernel_release("23.3.0")
+///     .with_hw_model("MacBookPro17,1");
+/// let what_if = WhatIf::new().os_version(MacOsVersion::new(15, 1, 0));
+/// let simulation = Detector::new().simulate(&system, &what_if);
+/// assert_eq!(simulation.changes()[0].rule_id(), "not-posix");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WhatIf {
+    values: BTreeMap<String, Option<String>>,
+}
+
+impl WhatIf {
+    /// Creates a what-if without any overrides.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Overrides the value of the key `name`.
+    pub fn value(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.values.insert(name.into(), Some(value.into()));
+        self
+    }
+
+    /// Makes the key `name` unavailable.
+    pub fn unset(mut self, name: impl Into<String>) -> Self {
+        self.values.insert(name.into(), None);
+        self
+    }
+
+    /// Overrides the macOS version, along with a matching Darwin kernel release.
+    ///
+    /// The Darwin minor version is one ahead of the macOS minor version on Big Sur, Monterey and
+    /// Ventura, up to `.6` for their last updates, equal to it since Sonoma, and `0` before Big
+    /// Sur. The build version is replaced by the build of `version`, or unset if it has none.
+    pub fn os_version(self, version: MacOsVersion) -> Self {
+        let release = version.darwin_major().map(|darwin| match version.major {
+            10 => format!("{}.0.0", darwin),
+            11..=13 => format!("{}.{}.0", darwin, (version.minor + 1).min(6)),
+            _ => format!("{}.{}.0", darwin, version.minor),
+        });
+        let product_version = MacOsVersion {
+            build: None,
+            ..version.clone()
+        };
+        let this = self.value(KERN_OSPRODUCTVERSION, product_version.to_string());
+        let this = match release {
+            Some(release) => this.value(KERN_OSRELEASE, release),
+            None => this.unset(KERN_OSRELEASE),
+        };
+        match version.build {
+            Some(build) => this.value(KERN_OSVERSION, build),
+            None => this.unset(KERN_OSVERSION),
+        }
+    }
+
+    /// Overrides the hardware model identifier (`hw.model`).
+    ///
+    /// For a known [`MacModel`], the processor is overridden to match its chip:
+    /// [`HW_OPTIONAL_ARM64`], and a [`MACHDEP_CPU_BRAND_STRING`] like `Apple M2`, or just `Intel`
+    /// for Intel Macs, which also can't run translated processes. Unknown models keep the
+    /// processor of the system.
+    pub fn hw_model(self, model: impl Into<String>) -> Self {
+        let model = model.into();
+        let chip = MacModel::lookup(&model).map(|model| model.chip);
+        let this = self.value(HW_MODEL, model);
+        match chip {
+            Some(Chip::Intel) => this
+                .value(HW_OPTIONAL_ARM64, "0")
+                .value(MACHDEP_CPU_BRAND_STRING, "Intel")
+                .unset(SYSCTL_PROC_TRANSLATED),
+            Some(chip) => this
+                .value(HW_OPTIONAL_ARM64, "1")
+                .value(MACHDEP_CPU_BRAND_STRING, format!("Apple {}", chip)),
+            None => this,
+        }
+    }
+
+    /// Whether there are no overrides.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Iterates over the overrides by key, with `None` for unset keys.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Option<&str>)> {
+        self.values
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_deref()))
+    }
+
+    /// Applies the overrides on top of `system`.
+    pub fn apply<'a>(&'a self, system: &'a dyn SystemInfo) -> impl SystemInfo + 'a {
+        Overlay {
+            system,
+            what_if: self,
+        }
+    }
+}
+
+impl Display for WhatIf {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, (name, value)) in self.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            match value {
+                Some(value) => write!(f, "{} = {:?}", name, value)?,
+                None => write!(f, "{} unset", name)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// [`SystemInfo`] with the overrides of a [`WhatIf`].
+struct Overlay<'a> {
+    system: &'a dyn SystemInfo,
+    what_if: &'a WhatIf,
+}
+
+impl SystemInfo for Overlay<'_> {
+    fn value_string(&self, name: &str) -> Result<String, Error> {
+        match self.what_if.values.get(name) {
+            Some(Some(value)) => Ok(value.clone()),
+            Some(None) => Err(Error::Missing(name.to_owned())),
+            None => self.system.value_string(name),
+        }
+    }
+}
+
+/// Reports of the same rules on a system and on the system with the overrides of a [`WhatIf`],
+/// see [`Detector::simulate`](crate::Detector::simulate).
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct Simulation {
+    /// Report on the system as is.
+    pub actual: Report,
+    /// Report on the system with the overrides.
+    pub simulated: Report,
+}
+
+impl Simulation {
+    /// Findings which would appear, disappear or change with the overrides, in rule order.
+    pub fn changes(&self) -> Vec<Change<'_>> {
+        self.simulated
+            .findings
+            .iter()
+            .filter_map(|simulated| {
+                let actual = self
+                    .actual
+                    .findings
+                    .iter()
+                    .find(|actual| actual.rule_id == simulated.rule_id)?;
+                match (actual.outcome.error(), simulated.outcome.error()) {
+                    (None, Some(_)) => Some(Change::Appeared(simulated)),
+                    (Some(_), None) => Some(Change::Disappeared(actual)),
+                    (Some(before), Some(after)) if before.to_string() != after.to_string() => {
+                        Some(Change::Changed { actual, simulated })
+                    }
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Serializes as `{"actual": ..., "simulated": ..., "changes": [...]}`.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Simulation {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("Simulation", 3)?;
+        state.serialize_field("actual", &self.actual)?;
+        state.serialize_field("simulated", &self.simulated)?;
+        state.serialize_field("changes", &self.changes())?;
+        state.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Simulation {
+    /// Serializes the simulation as pretty-printed JSON.
+    ///
+    /// # Errors
+    ///
+    /// Errors if serialization fails.
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+/// A finding which differs between the reports of a [`Simulation`].
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(tag = "change", rename_all = "kebab-case")
+)]
+pub enum Change<'a> {
+    /// The rule would fail or error, but passes now.
+    Appeared(&'a Finding),
+    /// The rule fails or errors now, but would pass.
+    Disappeared(&'a Finding),
+    /// The rule fails or errors either way, but with a different error.
+    Changed {
+        /// The finding on the system as is.
+        actual: &'a Finding,
+        /// The finding on the system with the overrides.
+        simulated: &'a Finding,
+    },
+}
+
+impl Change<'_> {
+    /// [`Rule::id`](crate::Rule::id) of the rule.
+    pub fn rule_id(&self) -> &str {
+        match self {
+            Change::Appeared(finding) | Change::Disappeared(finding) => &finding.rule_id,
+            Change::Changed { simulated, .. } => &simulated.rule_id,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Change, WhatIf};
+    use crate::{
+        Architecture, CpuRule, Detector, FakeSystem, MacOsVersion, Policy, PosixRule, SystemInfo,
+        HW_OPTIONAL_ARM64, MACHDEP_CPU_BRAND_STRING,
+    };
+
+    #[test]
+    fn test_apply() {
+        let system = FakeSystem::new()
+            .with_os_product_version("14.3.1")
+            .with_kernel_release("23.3.0")
+            .with_os_build("23D60")
+            .with_hw_model("MacBookPro17,1");
+        let what_if = WhatIf::new().os_version("15.1".parse().unwrap());
+        let overlay = what_if.apply(&system);
+        assert_eq!(overlay.os_product_version().unwrap(), "15.1");
+        assert_eq!(overlay.kernel_release().unwrap(), "24.1.0");
+        assert!(overlay.os_build().is_err());
+        assert_eq!(overlay.hw_model().unwrap(), "MacBookPro17,1");
+
+        let release = |version: &str| {
+            let what_if = WhatIf::new().os_version(version.parse().unwrap());
+            let release = what_if.apply(&system).kernel_release().unwrap();
+            release
+        };
+        assert_eq!(release("11.1"), "20.2.0");
+        assert_eq!(release("11.7.10"), "20.6.0");
+        assert_eq!(release("13.4"), "22.5.0");
+        assert_eq!(release("14.4.1"), "23.4.0");
+
+        let what_if = WhatIf::new().os_version("10.15.7 (19H15)".parse().unwrap());
+        let overlay = what_if.apply(&system);
+        assert_eq!(overlay.kernel_release().unwrap(), "19.0.0");
+        assert_eq!(overlay.os_build().unwrap(), "19H15");
+    }
+
+    #[test]
+    fn test_simulate() {
+        let system = FakeSystem::new()
+            .with_os_product_version("14.4.1")
+            .with_kernel_release("23.4.0")
+            .with_hw_model("MacBookPro17,1");
+        let detector = Detector::new();
+
+        let what_if = WhatIf::new().os_version(MacOsVersion::new(14, 3, 1));
+        let simulation = detector.simulate(&system, &what_if);
+        let changes = simulation.changes();
+        assert_eq!(changes.len(), 1);
+        assert!(
+            matches!(changes[0], Change::Disappeared(finding) if finding.rule_id == PosixRule::ID)
+        );
+
+        let what_if = WhatIf::new().hw_model("Mac15,12");
+        let simulation = detector.simulate(&system, &what_if);
+        let changes = simulation.changes();
+        assert_eq!(changes.len(), 1);
+        assert!(matches!(changes[0], Change::Changed { .. }));
+
+        let what_if = WhatIf::new().hw_model("MacBookPro16,1");
+        let changes = detector.simulate(&system, &what_if).changes().len();
+        assert_eq!(changes, 1);
+
+        assert!(detector
+            .simulate(&system, &WhatIf::new())
+            .changes()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_simulate_cpu() {
+        let intel = FakeSystem::new()
+            .with_os_product_version("14.3.1")
+            .with_kernel_release("23.3.0")
+            .with_hw_model("MacBookPro16,1")
+            .with_value(
+                MACHDEP_CPU_BRAND_STRING,
+                "Intel(R) Core(TM) i9-9880H CPU @ 2.30GHz",
+            );
+        let what_if = WhatIf::new().hw_model("Mac14,7");
+        let overlay = what_if.apply(&intel);
+        assert_eq!(overlay.value_string(HW_OPTIONAL_ARM64).unwrap(), "1");
+        assert_eq!(
+            overlay.value_string(MACHDEP_CPU_BRAND_STRING).unwrap(),
+            "Apple M2"
+        );
+
+        let mut policy = Policy::default();
+        policy.cpu.require_arch = vec![Architecture::Arm64];
+        let simulation = Detector::with_policy(&policy).simulate(&intel, &what_if);
+        let changes = simulation.changes();
+        assert!(changes
+            .iter()
+            .any(|change| matches!(change, Change::Disappeared(finding) if finding.rule_id == CpuRule::ID)));
+
+        let unknown = WhatIf::new().hw_model("Mac99,1");
+        assert!(unknown
+            .apply(&intel)
+            .value_string(HW_OPTIONAL_ARM64)
+            .is_err());
+        let back = WhatIf::new().hw_model("MacBookPro16,1");
+        assert_eq!(
+            back.apply(&overlay)
+                .value_string(MACHDEP_CPU_BRAND_STRING)
+                .unwrap(),
+            "Intel"
+        );
+    }
+}
@@ -0,0 +1,279 @@
+//! Recorded system information that checks can be replayed against.
+
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Write},
+    path::Path,
+    str::FromStr,
+};
+
+use crate::{Error, SystemInfo};
+
+/// System information values recorded from a Mac, keyed by sysctl name.
+///
+/// Snapshots are usually taken with [`Detector::capture`](crate::Detector::capture) and
+/// saved to a file, which contains one `name = "value"` line per value:
+///
+/// ```text
+/// # dikc-detector system snapshot
+/// hw.model = "MacBookPro16,1"
+/// kern.osproductversion = "14.4.1"
+/// kern.osrelease = "23.4.0"
+/// kern.osversion = "23E224"
+/// ```
+///
+/// Values are quoted, with `\\`, `\"`, `\n`, `\r`, `\t` and `\uXXXX` escapes.
+/// Empty lines and lines starting with `#` are ignored.
+///
+/// # Example
+///
+/// ```
+/// use dikc_detector::{check_with, SystemSnapshot};
+///
+/// let snapshot: SystemSnapshot = "
+///     kern.osproductversion = \"14.3.1\"
+///     kern.osrelease = \"23.3.0\"
+///     hw.model = \"MacBookPro17,1\"
+/// "
+/// .parse()
+/// .unwrap();
+/// assert!(check_with(&snapshot).is_ok());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct SystemSnapshot {
+    values: BTreeMap<String, String>,
+}
+
+impl SystemSnapshot {
+    /// Creates an empty snapshot.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads every key in `names` from `system`, leaving out values that can't be read.
+    pub fn capture<'a>(system: &dyn SystemInfo, names: impl IntoIterator<Item = &'a str>) -> Self {
+        let values = names
+            .into_iter()
+            .filter_map(|name| Some((name.to_owned(), system.value_string(name).ok()?)))
+            .collect();
+        Self { values }
+    }
+
+    /// Records `value` for the key `name`.
+    pub fn insert(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        self.values.insert(name.into(), value.into());
+    }
+
+    /// The recorded value of the key `name`.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(String::as_str)
+    }
+
+    /// Iterates over the recorded values in key order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.values
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    /// Loads a snapshot from the file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the file can't be read or is not a valid snapshot.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SnapshotError> {
+        std::fs::read_to_string(path)
+            .map_err(SnapshotError::Io)?
+            .parse()
+    }
+
+    /// Saves the snapshot to the file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the file can't be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SnapshotError> {
+        std::fs::write(path, self.to_string()).map_err(SnapshotError::Io)
+    }
+}
+
+impl SystemInfo for SystemSnapshot {
+    fn value_string(&self, name: &str) -> Result<String, Error> {
+        self.get(name)
+            .map(str::to_owned)
+            .ok_or_else(|| Error::Missing(name.to_owned()))
+    }
+}
+
+impl Extend<(String, String)> for SystemSnapshot {
+    fn extend<T: IntoIterator<Item = (String, String)>>(&mut self, iter: T) {
+        self.values.extend(iter);
+    }
+}
+
+impl FromIterator<(String, String)> for SystemSnapshot {
+    fn from_iter<T: IntoIterator<Item = (String, String)>>(iter: T) -> Self {
+        Self {
+            values: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl Display for SystemSnapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "# dikc-detector system snapshot")?;
+        for (name, value) in &self.values {
+            write!(f, "{} = \"", name)?;
+            for c in value.chars() {
+                match c {
+                    '\\' => f.write_str("\\\\")?,
+                    '"' => f.write_str("\\\"")?,
+                    '\n' => f.write_str("\\n")?,
+                    '\r' => f.write_str("\\r")?,
+                    '\t' => f.write_str("\\t")?,
+                    c if c.is_control() => write!(f, "\\u{:04X}", c as u32)?,
+                    c => f.write_char(c)?,
+                }
+            }
+            writeln!(f, "\"")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for SystemSnapshot {
+    type Err = SnapshotError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut snapshot = Self::new();
+        for (index, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let err = || SnapshotError::Parse { line: index + 1 };
+            let (name, value) = line.split_once('=').ok_or_else(err)?;
+            let name = name.trim();
+            let value = value
+                .trim()
+                .strip_prefix('"')
+                .and_then(|value| value.strip_suffix('"'))
+                .ok_or_else(err)?;
+            if name.is_empty() {
+                return Err(err());
+            }
+            snapshot.insert(name, unescape(value).ok_or_else(err)?);
+        }
+        Ok(snapshot)
+    }
+}
+
+/// Reverses the escapes written by `Display for SystemSnapshot`.
+fn unescape(value: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.push(match chars.next()? {
+                '\\' => '\\',
+                '"' => '"',
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                'u' => {
+                    let hex: String = chars.by_ref().take(4).collect();
+                    if hex.len() != 4 {
+                        return None;
+                    }
+                    char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?
+                }
+                _ => return None,
+            }),
+            '"' => return None,
+            c => unescaped.push(c),
+        }
+    }
+    Some(unescaped)
+}
+
+/// Error when loading a [`SystemSnapshot`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum SnapshotError {
+    /// The snapshot file can't be read or written.
+    Io(std::io::Error),
+    /// The snapshot is malformed.
+    Parse {
+        /// 1-based line number of the malformed line.
+        line: usize,
+    },
+}
+
+impl Display for SnapshotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnapshotError::Io(err) => write!(f, "can't access snapshot: {}", err),
+            SnapshotError::Parse { line } => write!(f, "invalid snapshot at line {}", line),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SnapshotError::Io(err) => Some(err),
+            SnapshotError::Parse { .. } => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SystemSnapshot;
+    use crate::{Detector, FakeSystem, HW_MODEL, KERN_OSPRODUCTVERSION};
+
+    #[test]
+    fn test_round_trip() {
+        let mut snapshot = SystemSnapshot::new();
+        snapshot.insert(HW_MODEL, "MacBookPro16,1");
+        snapshot.insert("machdep.cpu.brand_string", "Intel(R) \"Core\"\ti9\\\u{1}\n");
+        let text = snapshot.to_string();
+        assert!(text.contains("hw.model = \"MacBookPro16,1\"\n"));
+        assert_eq!(text.parse::<SystemSnapshot>().unwrap(), snapshot);
+
+        for bad in [
+            "hw.model",
+            "hw.model = MacBookPro16,1",
+            " = \"\"",
+            "a = \"\\q\"",
+            "a = \"\"\"",
+            "a = \"\\u12\"",
+        ] {
+            assert!(bad.parse::<SystemSnapshot>().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_capture() {
+        let system = FakeSystem::new()
+            .with_os_product_version("14.4.1")
+            .with_kernel_release("23.4.0")
+            .with_hw_model("MacBookPro16,1")
+            .with_value("hw.memsize", "17179869184");
+        let snapshot = Detector::new().capture(&system);
+        assert_eq!(snapshot.get(KERN_OSPRODUCTVERSION), Some("14.4.1"));
+        assert_eq!(snapshot.get(HW_MODEL), Some("MacBookPro16,1"));
+        assert_eq!(snapshot.get("hw.memsize"), None);
+
+        let replayed = Detector::new().report(&snapshot);
+        assert_eq!(
+            replayed.verdict(),
+            Detector::new().report(&system).verdict()
+        );
+    }
+}
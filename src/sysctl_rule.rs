@@ -0,0 +1,528 @@
+//! Rules comparing any sysctl value, configurable from a policy.
+
+use std::{cmp::Ordering, fmt::Display};
+
+use regex_lite::Regex;
+
+use crate::{system::struct_field, Error, MacOsVersion, Rule, SystemInfo, VersionRange};
+
+/// Comparison of a sysctl value with an expected value.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum Comparator {
+    /// The value equals the string.
+    Equals(String),
+    /// The value doesn't equal the string.
+    NotEquals(String),
+    /// The value matches the regular expression somewhere.
+    Regex(Regex),
+    /// The value is a number within the inclusive bounds.
+    Range {
+        /// Smallest allowed value.
+        min: Option<Number>,
+        /// Largest allowed value.
+        max: Option<Number>,
+    },
+    /// The value is a macOS version within the range.
+    Version(VersionRange),
+}
+
+/// Bound of a [`Comparator::Range`].
+///
+/// Integer bounds are compared exactly with integer values, even above 2^53 where `f64` loses
+/// precision, e.g. for `hw.memsize`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(untagged)
+)]
+pub enum Number {
+    /// An integer.
+    Integer(i64),
+    /// A floating-point number.
+    Float(f64),
+}
+
+impl From<i64> for Number {
+    fn from(number: i64) -> Self {
+        Number::Integer(number)
+    }
+}
+
+impl From<f64> for Number {
+    fn from(number: f64) -> Self {
+        Number::Float(number)
+    }
+}
+
+impl Display for Number {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Number::Integer(number) => write!(f, "{}", number),
+            Number::Float(number) => write!(f, "{}", number),
+        }
+    }
+}
+
+/// A value compared by [`Comparator::Range`], wide enough for every 64-bit sysctl.
+#[derive(Debug, Clone, Copy)]
+enum Value {
+    Integer(i128),
+    Float(f64),
+}
+
+impl Value {
+    fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        match value.parse() {
+            Ok(integer) => Some(Value::Integer(integer)),
+            Err(_) => value
+                .parse::<f64>()
+                .ok()
+                .filter(|float| float.is_finite())
+                .map(Value::Float),
+        }
+    }
+
+    fn cmp_bound(self, bound: Number) -> Option<Ordering> {
+        match (self, bound) {
+            (Value::Integer(value), Number::Integer(bound)) => Some(value.cmp(&i128::from(bound))),
+            (Value::Integer(value), Number::Float(bound)) => (value as f64).partial_cmp(&bound),
+            (Value::Float(value), Number::Integer(bound)) => value.partial_cmp(&(bound as f64)),
+            (Value::Float(value), Number::Float(bound)) => value.partial_cmp(&bound),
+        }
+    }
+}
+
+/// Regular expressions are equal if their patterns are.
+impl PartialEq for Comparator {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Comparator::Equals(a), Comparator::Equals(b))
+            | (Comparator::NotEquals(a), Comparator::NotEquals(b)) => a == b,
+            (Comparator::Regex(a), Comparator::Regex(b)) => a.as_str() == b.as_str(),
+            (
+                Comparator::Range { min, max },
+                Comparator::Range {
+                    min: other_min,
+                    max: other_max,
+                },
+            ) => min == other_min && max == other_max,
+            (Comparator::Version(a), Comparator::Version(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Comparator {
+    /// Whether `value` of the sysctl `key` satisfies the comparison.
+    ///
+    /// # Errors
+    ///
+    /// Errors with [`Error::UnexpectedValue`] if a range is compared with a value that is not a
+    /// number, or a version range with a value that is not a version. Like the built-in rules
+    /// reading numbers and processor brands, a value that can't be understood is a failure of the
+    /// rule, not of reading the system.
+    pub fn compare(&self, key: &str, value: &str) -> Result<bool, Error> {
+        let unexpected = |expected: &str| Error::UnexpectedValue {
+            key: key.to_owned(),
+            value: value.to_owned(),
+            expected: expected.to_owned(),
+        };
+        Ok(match self {
+            Comparator::Equals(expected) => value == expected,
+            Comparator::NotEquals(expected) => value != expected,
+            Comparator::Regex(regex) => regex.is_match(value),
+            Comparator::Range { min, max } => {
+                let number = Value::parse(value).ok_or_else(|| unexpected("a number"))?;
+                min.map_or(true, |min| {
+                    number.cmp_bound(min).is_some_and(Ordering::is_ge)
+                }) && max.map_or(true, |max| {
+                    number.cmp_bound(max).is_some_and(Ordering::is_le)
+                })
+            }
+            Comparator::Version(range) => range.contains(
+                &value
+                    .parse::<MacOsVersion>()
+                    .map_err(|_| unexpected("a macOS version"))?,
+            ),
+        })
+    }
+}
+
+/// Describes the expected value, e.g. `at least 17179869184`.
+impl Display for Comparator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Comparator::Equals(expected) => write!(f, "equal to `{}`", expected),
+            Comparator::NotEquals(expected) => write!(f, "not equal to `{}`", expected),
+            Comparator::Regex(regex) => write!(f, "matching `{}`", regex),
+            Comparator::Range {
+                min: Some(min),
+                max: Some(max),
+            } => write!(f, "between {} and {}", min, max),
+            Comparator::Range { min: Some(min), .. } => write!(f, "at least {}", min),
+            Comparator::Range { max: Some(max), .. } => write!(f, "at most {}", max),
+            Comparator::Range { .. } => write!(f, "a number"),
+            Comparator::Version(range) => write!(f, "a version in {}", range),
+        }
+    }
+}
+
+/// Rule comparing the value of any sysctl key, or of a field of a struct-backed one, e.g.
+/// `hw.memsize` or the `used` field of `vm.swapusage`.
+///
+/// Usually configured in the `[[sysctl]]` tables of a [`Policy`](crate::Policy), with one of
+/// `equals`, `not_equals`, `regex`, `min` and/or `max`, or `version`:
+///
+/// ```toml
+/// [[sysctl]]
+/// id = "enough-memory"
+/// key = "hw.memsize"
+/// min = 17179869184
+///
+/// [[sysctl]]
+/// id = "no-swap"
+/// key = "vm.swapusage"
+/// field = "used"
+/// max = 0
+/// ```
+///
+/// # Example
+///
+/// ```
+/// use dikc_detector::{Comparator, Detector, FakeSystem, Number, SysctlRule};
+///
+/// let rule = SysctlRule::new("enough-memory", "hw.memsize", Comparator::Range {
+///     min: Some(Number::Integer(17179869184)),
+///     max: None,
+/// });
+/// let detector = Detector::empty().rule(rule);
+/// let system = FakeSystem::new().with_value("hw.memsize", "8589934592");
+/// assert!(detector.run(&system).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "SysctlRuleConfig", into = "SysctlRuleConfig")
+)]
+#[non_exhaustive]
+pub struct SysctlRule {
+    /// [`Rule::id`] of the rule.
+    pub id: String,
+    /// The sysctl key.
+    pub key: String,
+    /// The field of a struct-backed value, see [`SystemInfo`].
+    pub field: Option<String>,
+    /// Expected value.
+    pub comparator: Comparator,
+    description: String,
+}
+
+impl SysctlRule {
+    /// Creates a rule with the ID `id` comparing the value of `key`.
+    pub fn new(id: impl Into<String>, key: impl Into<String>, comparator: Comparator) -> Self {
+        let mut rule = Self {
+            id: id.into(),
+            key: key.into(),
+            field: None,
+            comparator,
+            description: String::new(),
+        };
+        rule.description = rule.default_description();
+        rule
+    }
+
+    /// Compares the field `field` of the struct-backed value instead of the whole value.
+    pub fn with_field(mut self, field: impl Into<String>) -> Self {
+        let generated = self.description == self.default_description();
+        self.field = Some(field.into());
+        if generated {
+            self.description = self.default_description();
+        }
+        self
+    }
+
+    /// Replaces the generated [`Rule::description`].
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = description.into();
+        self
+    }
+
+    /// The key and field, e.g. `vm.swapusage.used`.
+    fn name(&self) -> String {
+        match &self.field {
+            Some(field) => format!("{}.{}", self.key, field),
+            None => self.key.clone(),
+        }
+    }
+
+    fn default_description(&self) -> String {
+        format!("`{}` is {}", self.name(), self.comparator)
+    }
+}
+
+impl Rule for SysctlRule {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn evaluate(&self, system: &dyn SystemInfo) -> Result<(), Error> {
+        let value = system.value_string(&self.key)?;
+        let value = match &self.field {
+            Some(field) => struct_field(&value, field)
+                .ok_or_else(|| Error::Missing(self.name()))?
+                .to_owned(),
+            None => value,
+        };
+        if self.comparator.compare(&self.name(), &value)? {
+            Ok(())
+        } else {
+            Err(Error::UnexpectedValue {
+                key: self.name(),
+                value,
+                expected: self.comparator.to_string(),
+            })
+        }
+    }
+}
+
+/// Flat TOML representation of a [`SysctlRule`] with exactly one comparator.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SysctlRuleConfig {
+    id: String,
+    key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    field: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    equals: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    not_equals: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    regex: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    min: Option<Number>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max: Option<Number>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    version: Option<VersionRange>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<SysctlRuleConfig> for SysctlRule {
+    type Error = String;
+
+    fn try_from(config: SysctlRuleConfig) -> Result<Self, Self::Error> {
+        let mut comparators = Vec::new();
+        if let Some(expected) = config.equals {
+            comparators.push(Comparator::Equals(expected));
+        }
+        if let Some(expected) = config.not_equals {
+            comparators.push(Comparator::NotEquals(expected));
+        }
+        if let Some(regex) = config.regex {
+            let regex = Regex::new(&regex).map_err(|err| format!("invalid `regex`: {}", err))?;
+            comparators.push(Comparator::Regex(regex));
+        }
+        if config.min.is_some() || config.max.is_some() {
+            comparators.push(Comparator::Range {
+                min: config.min,
+                max: config.max,
+            });
+        }
+        if let Some(range) = config.version {
+            comparators.push(Comparator::Version(range));
+        }
+        let comparator = match <[Comparator; 1]>::try_from(comparators) {
+            Ok([comparator]) => comparator,
+            Err(_) => {
+                return Err(format!(
+                    "sysctl rule `{}` needs exactly one of `equals`, `not_equals`, `regex`, \
+                     `min`/`max` or `version`",
+                    config.id
+                ))
+            }
+        };
+        let mut rule = SysctlRule::new(config.id, config.key, comparator);
+        if let Some(field) = config.field {
+            rule = rule.with_field(field);
+        }
+        if let Some(description) = config.description {
+            rule = rule.with_description(description);
+        }
+        Ok(rule)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<SysctlRule> for SysctlRuleConfig {
+    fn from(rule: SysctlRule) -> Self {
+        let description =
+            (rule.description != rule.default_description()).then(|| rule.description.clone());
+        let mut config = SysctlRuleConfig {
+            id: rule.id,
+            key: rule.key,
+            field: rule.field,
+            description,
+            equals: None,
+            not_equals: None,
+            regex: None,
+            min: None,
+            max: None,
+            version: None,
+        };
+        match rule.comparator {
+            Comparator::Equals(expected) => config.equals = Some(expected),
+            Comparator::NotEquals(expected) => config.not_equals = Some(expected),
+            Comparator::Regex(regex) => config.regex = Some(regex.as_str().to_owned()),
+            Comparator::Range { min, max } => (config.min, config.max) = (min, max),
+            Comparator::Version(range) => config.version = Some(range),
+        }
+        config
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use regex_lite::Regex;
+
+    use super::{Comparator, Number, SysctlRule};
+    use crate::{Detector, Error, FakeSystem, Outcome, Rule};
+
+    #[test]
+    fn test_compare() {
+        let range = Comparator::Range {
+            min: Some(Number::Integer(8)),
+            max: Some(Number::Float(16.0)),
+        };
+        assert!(range.compare("a", "8").unwrap());
+        assert!(range.compare("a", " 15.5\n").unwrap());
+        assert!(!range.compare("a", "16.5").unwrap());
+        assert!(matches!(
+            range.compare("a", "lots"),
+            Err(Error::UnexpectedValue { key, value, expected })
+                if key == "a" && value == "lots" && expected == "a number"
+        ));
+        assert!(range.compare("a", "NaN").is_err());
+        assert!(Comparator::Regex(Regex::new("^Apple M[0-9]").unwrap())
+            .compare("a", "Apple M2 Pro")
+            .unwrap());
+        assert!(!Comparator::NotEquals("x86_64".to_owned())
+            .compare("a", "x86_64")
+            .unwrap());
+        let version = Comparator::Version(">=14.0".parse().unwrap());
+        assert!(version.compare("a", "14.4.1").unwrap());
+        assert!(matches!(
+            version.compare("a", "Sonoma"),
+            Err(Error::UnexpectedValue { key, value, expected })
+                if key == "a" && value == "Sonoma" && expected == "a macOS version"
+        ));
+
+        // 2^53 + 1 and 2^53 are the same `f64`.
+        let exact = Comparator::Range {
+            min: Some(Number::Integer(9007199254740993)),
+            max: None,
+        };
+        assert!(exact.compare("a", "9007199254740993").unwrap());
+        assert!(!exact.compare("a", "9007199254740992").unwrap());
+        let unsigned = Comparator::Range {
+            min: None,
+            max: Some(Number::Integer(i64::MAX)),
+        };
+        assert!(!unsigned.compare("a", "18446744073709551615").unwrap());
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let system = FakeSystem::new()
+            .with_value("hw.memsize", "17179869184")
+            .with_value(
+                "vm.swapusage",
+                "total=2048 avail=1024 used=1024 pagesize=16384 encrypted=1",
+            );
+        let memory = SysctlRule::new(
+            "enough-memory",
+            "hw.memsize",
+            Comparator::Range {
+                min: Some(Number::Integer(17179869184)),
+                max: None,
+            },
+        );
+        assert!(memory.evaluate(&system).is_ok());
+        assert_eq!(memory.description(), "`hw.memsize` is at least 17179869184");
+
+        let swap = SysctlRule::new(
+            "no-swap",
+            "vm.swapusage",
+            Comparator::Range {
+                min: None,
+                max: Some(Number::Integer(0)),
+            },
+        )
+        .with_field("used");
+        assert_eq!(swap.description(), "`vm.swapusage.used` is at most 0");
+        assert!(matches!(
+            swap.evaluate(&system),
+            Err(Error::UnexpectedValue { key, value, .. }) if key == "vm.swapusage.used" && value == "1024"
+        ));
+
+        let missing = swap.clone().with_field("free");
+        assert!(
+            matches!(missing.evaluate(&system), Err(Error::Missing(key)) if key == "vm.swapusage.free")
+        );
+
+        // Values that can't be understood fail the rule under either comparator.
+        let garbled = FakeSystem::new()
+            .with_value("hw.memsize", "lots")
+            .with_value("kern.osproductversion", "Sonoma");
+        let os = SysctlRule::new(
+            "new-os",
+            "kern.osproductversion",
+            Comparator::Version(">=14.0".parse().unwrap()),
+        );
+        let report = Detector::empty().rule(memory).rule(os).report(&garbled);
+        assert!(report.findings.iter().all(|finding| matches!(
+            finding.outcome,
+            Outcome::Fail(Error::UnexpectedValue { .. })
+        )));
+    }
+
+    #[test]
+    #[cfg(feature = "toml")]
+    fn test_from_toml() {
+        let policy = crate::Policy::from_toml(
+            "[[sysctl]]\nid = \"enough-memory\"\nkey = \"hw.memsize\"\nmin = 17179869184\n\n\
+             [[sysctl]]\nid = \"apple-silicon\"\nkey = \"machdep.cpu.brand_string\"\nregex = \"^Apple\"\n",
+        )
+        .unwrap();
+        assert_eq!(policy.sysctl.len(), 2);
+        assert_eq!(
+            policy.sysctl[0].comparator,
+            Comparator::Range {
+                min: Some(Number::Integer(17179869184)),
+                max: None
+            }
+        );
+        assert!(matches!(policy.sysctl[1].comparator, Comparator::Regex(_)));
+
+        assert!(crate::Policy::from_toml("[[sysctl]]\nid = \"a\"\nkey = \"b\"\n").is_err());
+        assert!(crate::Policy::from_toml(
+            "[[sysctl]]\nid = \"a\"\nkey = \"b\"\nequals = \"c\"\nregex = \"d\"\n"
+        )
+        .is_err());
+        assert!(
+            crate::Policy::from_toml("[[sysctl]]\nid = \"a\"\nkey = \"b\"\nregex = \"(\"\n")
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,344 @@
+//! Sources of system information that checks are evaluated against.
+
+use std::collections::HashMap;
+
+#[cfg(target_os = "macos")]
+use sysctl::{Ctl, CtlValue, Sysctl, SysctlError};
+
+use crate::Error;
+
+/// Sysctl key holding the hardware model identifier, e.g. `MacBookPro16,1`.
+pub const HW_MODEL: &str = "hw.model";
+/// Sysctl key holding the macOS product version, e.g. `14.4.1`.
+pub const KERN_OSPRODUCTVERSION: &str = "kern.osproductversion";
+
+/// Sysctl key holding the Darwin kernel release, e.g. `23.4.0`.
+pub const KERN_OSRELEASE: &str = "kern.osrelease";
+/// Sysctl key holding the macOS build version, e.g. `23E214`.
+pub const KERN_OSVERSION: &str = "kern.osversion";
+/// Sysctl key holding the hostname.
+pub const KERN_HOSTNAME: &str = "kern.hostname";
+/// Sysctl key holding the physical memory in bytes.
+pub const HW_MEMSIZE: &str = "hw.memsize";
+/// Sysctl key holding the number of physical CPU cores.
+pub const HW_PHYSICALCPU: &str = "hw.physicalcpu";
+/// Sysctl key holding the number of logical CPU cores.
+pub const HW_LOGICALCPU: &str = "hw.logicalcpu";
+/// Sysctl key which is `1` on Apple silicon, and `0` or missing on Intel Macs.
+pub const HW_OPTIONAL_ARM64: &str = "hw.optional.arm64";
+/// Sysctl key holding the marketing name of the processor, e.g. `Apple M2 Pro`.
+pub const MACHDEP_CPU_BRAND_STRING: &str = "machdep.cpu.brand_string";
+/// Sysctl key which is `1` in virtual machines, and `0` or missing otherwise.
+pub const KERN_HV_VMM_PRESENT: &str = "kern.hv_vmm_present";
+/// Sysctl key which is `1` in processes translated by Rosetta, and `0` or missing otherwise.
+pub const SYSCTL_PROC_TRANSLATED: &str = "sysctl.proc_translated";
+
+/// I/O Kit property holding the hardware UUID, read with `ioreg` rather than `sysctl`.
+pub const IOPLATFORM_UUID: &str = "IOPlatformUUID";
+/// I/O Kit property holding the serial number, read with `ioreg` rather than `sysctl`.
+pub const IOPLATFORM_SERIAL_NUMBER: &str = "IOPlatformSerialNumber";
+/// Key holding the free space on the root volume in bytes, read with `df` rather than `sysctl`.
+pub const ROOT_VOLUME_FREE: &str = "fs.root.free";
+
+/// Every key read by the built-in rules.
+pub(crate) const KEYS: &[&str] = &[
+    HW_MODEL,
+    KERN_OSPRODUCTVERSION,
+    KERN_OSRELEASE,
+    KERN_OSVERSION,
+];
+
+/// Provider of the system information checks are evaluated against.
+///
+/// Every value is addressed by its sysctl name, so implementations only need
+/// to provide [`SystemInfo::value_string`]; the remaining methods are
+/// shorthands for the keys the built-in checks read.
+///
+/// Integers are written in decimal. Struct-backed sysctls are written as space-separated
+/// `field=value` pairs, e.g. `vm.swapusage` as
+/// `total=2147483648 avail=1073741824 used=1073741824 pagesize=16384 encrypted=1`.
+pub trait SystemInfo {
+    /// Reads the value of the key `name` as a string.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the value is unavailable or can't be read.
+    fn value_string(&self, name: &str) -> Result<String, Error>;
+
+    /// Reads the macOS product version (`kern.osproductversion`).
+    ///
+    /// # Errors
+    ///
+    /// Errors if the value is unavailable or can't be read.
+    fn os_product_version(&self) -> Result<String, Error> {
+        self.value_string(KERN_OSPRODUCTVERSION)
+    }
+
+    /// Reads the hardware model identifier (`hw.model`).
+    ///
+    /// # Errors
+    ///
+    /// Errors if the value is unavailable or can't be read.
+    fn hw_model(&self) -> Result<String, Error> {
+        self.value_string(HW_MODEL)
+    }
+
+    /// Reads the Darwin kernel release (`kern.osrelease`).
+    ///
+    /// # Errors
+    ///
+    /// Errors if the value is unavailable or can't be read.
+    fn kernel_release(&self) -> Result<String, Error> {
+        self.value_string(KERN_OSRELEASE)
+    }
+
+    /// Reads the macOS build version (`kern.osversion`).
+    ///
+    /// # Errors
+    ///
+    /// Errors if the value is unavailable or can't be read.
+    fn os_build(&self) -> Result<String, Error> {
+        self.value_string(KERN_OSVERSION)
+    }
+
+    /// Reads the hostname (`kern.hostname`).
+    ///
+    /// # Errors
+    ///
+    /// Errors if the value is unavailable or can't be read.
+    fn hostname(&self) -> Result<String, Error> {
+        self.value_string(KERN_HOSTNAME)
+    }
+
+    /// Reads the hardware UUID (`IOPlatformUUID`).
+    ///
+    /// # Errors
+    ///
+    /// Errors if the value is unavailable or can't be read.
+    fn hardware_uuid(&self) -> Result<String, Error> {
+        self.value_string(IOPLATFORM_UUID)
+    }
+
+    /// Reads the serial number (`IOPlatformSerialNumber`).
+    ///
+    /// # Errors
+    ///
+    /// Errors if the value is unavailable or can't be read.
+    fn serial_number(&self) -> Result<String, Error> {
+        self.value_string(IOPLATFORM_SERIAL_NUMBER)
+    }
+}
+
+/// System information read live from `sysctl`, from `ioreg` for [`IOPLATFORM_UUID`] and
+/// [`IOPLATFORM_SERIAL_NUMBER`], and from `df` for [`ROOT_VOLUME_FREE`].
+///
+/// Keys which don't exist on this Mac error with [`Error::Missing`]. Only macOS is supported,
+/// every read errors with [`Error::UnsupportedPlatform`] elsewhere.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LiveSystem;
+
+impl SystemInfo for LiveSystem {
+    #[cfg(target_os = "macos")]
+    fn value_string(&self, name: &str) -> Result<String, Error> {
+        match name {
+            IOPLATFORM_UUID | IOPLATFORM_SERIAL_NUMBER => io_platform_property(name),
+            ROOT_VOLUME_FREE => root_volume_free(),
+            _ => {
+                let ctl = Ctl::new(name).map_err(|err| match err {
+                    SysctlError::NotFound(_) => Error::Missing(name.to_owned()),
+                    err => err.into(),
+                })?;
+                match ctl.value()? {
+                    CtlValue::Struct(bytes) => Ok(format_struct(&ctl.info()?.fmt, &bytes)),
+                    value => Ok(value.to_string()),
+                }
+            }
+        }
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    fn value_string(&self, _name: &str) -> Result<String, Error> {
+        Err(Error::UnsupportedPlatform)
+    }
+}
+
+/// Formats the value of a struct-backed sysctl with the format `fmt`, e.g. `S,timeval`, as
+/// `field=value` pairs, or as hex bytes for unknown structs.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn format_struct(fmt: &str, bytes: &[u8]) -> String {
+    let int = |offset: usize, len: usize| -> Option<u64> {
+        let mut buf = [0; 8];
+        buf[..len].copy_from_slice(bytes.get(offset..offset + len)?);
+        Some(u64::from_le_bytes(buf))
+    };
+    let fields: Option<Vec<(&str, String)>> = match fmt.strip_prefix("S,") {
+        Some("timeval") => (|| {
+            Some(vec![
+                ("sec", int(0, 8)?.to_string()),
+                ("usec", int(8, 4)?.to_string()),
+            ])
+        })(),
+        Some("xsw_usage") => (|| {
+            Some(vec![
+                ("total", int(0, 8)?.to_string()),
+                ("avail", int(8, 8)?.to_string()),
+                ("used", int(16, 8)?.to_string()),
+                ("pagesize", int(24, 4)?.to_string()),
+                ("encrypted", int(28, 4)?.to_string()),
+            ])
+        })(),
+        Some("loadavg") => (|| {
+            let scale = int(16, 8)? as f64;
+            Some(vec![
+                ("load1", format!("{:.2}", int(0, 4)? as f64 / scale)),
+                ("load5", format!("{:.2}", int(4, 4)? as f64 / scale)),
+                ("load15", format!("{:.2}", int(8, 4)? as f64 / scale)),
+            ])
+        })(),
+        Some("clockinfo") => (|| {
+            Some(vec![
+                ("hz", int(0, 4)?.to_string()),
+                ("tick", int(4, 4)?.to_string()),
+                ("tickadj", int(8, 4)?.to_string()),
+                ("stathz", int(12, 4)?.to_string()),
+                ("profhz", int(16, 4)?.to_string()),
+            ])
+        })(),
+        _ => None,
+    };
+    match fields {
+        Some(fields) => fields
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join(" "),
+        None => bytes.iter().map(|byte| format!("{:02x}", byte)).collect(),
+    }
+}
+
+/// The value of `field` in a struct-backed value written as `field=value` pairs.
+pub(crate) fn struct_field<'a>(value: &'a str, field: &str) -> Option<&'a str> {
+    value
+        .split_whitespace()
+        .filter_map(|pair| pair.split_once('='))
+        .find_map(|(name, value)| (name == field).then_some(value))
+}
+
+/// Reads a string property of the `IOPlatformExpertDevice` from the output of `ioreg`, which
+/// has lines like `"IOPlatformUUID" = "..."`.
+#[cfg(target_os = "macos")]
+fn io_platform_property(name: &str) -> Result<String, Error> {
+    let output = std::process::Command::new("/usr/sbin/ioreg")
+        .args(["-rd1", "-c", "IOPlatformExpertDevice"])
+        .output()
+        .map_err(Error::Io)?;
+    let prefix = format!("\"{}\" = \"", name);
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| {
+            let value = line.trim().strip_prefix(&prefix)?.strip_suffix('"')?;
+            Some(value.to_owned())
+        })
+        .ok_or_else(|| Error::Missing(name.to_owned()))
+}
+
+/// Reads the free space on the root volume from the output of `df -Pk /`, whose second line
+/// holds the available 1024-byte blocks in its fourth column.
+#[cfg(target_os = "macos")]
+fn root_volume_free() -> Result<String, Error> {
+    let output = std::process::Command::new("/bin/df")
+        .args(["-Pk", "/"])
+        .output()
+        .map_err(Error::Io)?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .nth(1)
+        .and_then(|line| line.split_whitespace().nth(3)?.parse::<u64>().ok())
+        .map(|blocks| (blocks * 1024).to_string())
+        .ok_or_else(|| Error::Missing(ROOT_VOLUME_FREE.to_owned()))
+}
+
+/// In-memory system information, for running checks against recorded machine profiles.
+///
+/// # Example
+///
+/// ```
+/// use dikc_detector::{check_with, FakeSystem};
+///
+/// let system = FakeSystem::new()
+///     .with_os_product_version("14.3.1")
+///     .with_kernel_release("23.3.0")
+///     .with_hw_model("MacBookPro17,1");
+/// assert!(check_with(&system).is_ok());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FakeSystem {
+    values: HashMap<String, String>,
+}
+
+impl FakeSystem {
+    /// Creates a fake system without any values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the value of the key `name`.
+    pub fn with_value(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.values.insert(name.into(), value.into());
+        self
+    }
+
+    /// Sets the macOS product version (`kern.osproductversion`).
+    pub fn with_os_product_version(self, value: impl Into<String>) -> Self {
+        self.with_value(KERN_OSPRODUCTVERSION, value)
+    }
+
+    /// Sets the hardware model identifier (`hw.model`).
+    pub fn with_hw_model(self, value: impl Into<String>) -> Self {
+        self.with_value(HW_MODEL, value)
+    }
+
+    /// Sets the Darwin kernel release (`kern.osrelease`).
+    pub fn with_kernel_release(self, value: impl Into<String>) -> Self {
+        self.with_value(KERN_OSRELEASE, value)
+    }
+
+    /// Sets the macOS build version (`kern.osversion`).
+    pub fn with_os_build(self, value: impl Into<String>) -> Self {
+        self.with_value(KERN_OSVERSION, value)
+    }
+}
+
+impl SystemInfo for FakeSystem {
+    fn value_string(&self, name: &str) -> Result<String, Error> {
+        self.values
+            .get(name)
+            .cloned()
+            .ok_or_else(|| Error::Missing(name.to_owned()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{format_struct, struct_field};
+
+    #[test]
+    fn test_format_struct() {
+        let mut swap = Vec::new();
+        swap.extend(2_147_483_648u64.to_le_bytes());
+        swap.extend(1_073_741_824u64.to_le_bytes());
+        swap.extend(1_073_741_824u64.to_le_bytes());
+        swap.extend(16_384u32.to_le_bytes());
+        swap.extend(1u32.to_le_bytes());
+        let value = format_struct("S,xsw_usage", &swap);
+        assert_eq!(
+            value,
+            "total=2147483648 avail=1073741824 used=1073741824 pagesize=16384 encrypted=1"
+        );
+        assert_eq!(struct_field(&value, "used"), Some("1073741824"));
+        assert_eq!(struct_field(&value, "free"), None);
+
+        assert_eq!(format_struct("S,timeval", &[1, 0]), "0100");
+        assert_eq!(format_struct("S,unknown", &[0xab, 0x01]), "ab01");
+    }
+}
@@ -0,0 +1,591 @@
+//! macOS version numbers.
+
+use std::{cmp::Ordering, fmt::Display, ops::Bound, str::FromStr};
+
+/// A macOS version such as `14.4`, `14.4.1` or `13.4.1 (a) (22F770820d)`.
+///
+/// A version may carry the letter of a Rapid Security Response, e.g. `(a)`, and a build
+/// identifier, e.g. `(22F770820d)`, in that order.
+///
+/// Versions are ordered by their components, a missing patch component is the same as `0`.
+/// A Rapid Security Response is newer than the version it applies to. Equality and ordering
+/// include the build, so `14.4 (23E214)` is not equal to `14.4`; use a [`VersionRange`], whose
+/// bounds ignore the build unless they have one, to match versions regardless of their build.
+///
+/// The Rapid Security Response letter is only known if the parsed string has it: the live
+/// `kern.osproductversion` never does, so versions read from a Mac carry no letter unless a
+/// snapshot or [`WhatIf`](crate::WhatIf) provides one.
+///
+/// # Example
+///
+/// ```
+/// use dikc_detector::MacOsVersion;
+///
+/// let version: MacOsVersion = "14.4.1".parse().unwrap();
+/// assert!(version > MacOsVersion::new(14, 4, 0));
+/// assert_eq!(version.to_string(), "14.4.1");
+///
+/// let rsr: MacOsVersion = "13.4.1 (c)".parse().unwrap();
+/// assert_eq!(rsr.rsr, Some('c'));
+/// assert!(rsr > MacOsVersion::new(13, 4, 1));
+///
+/// let build: MacOsVersion = "14.4 (23E214)".parse().unwrap();
+/// assert_ne!(build, MacOsVersion::new(14, 4, 0));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "String", into = "String")
+)]
+pub struct MacOsVersion {
+    /// Major version, e.g. `14` for Sonoma.
+    pub major: u32,
+    /// Minor version.
+    pub minor: u32,
+    /// Patch version, `0` if absent.
+    pub patch: u32,
+    /// Letter of the Rapid Security Response applied on top, e.g. `a` for `13.4.1 (a)`.
+    pub rsr: Option<char>,
+    /// Build identifier, e.g. `23E224`.
+    pub build: Option<String>,
+}
+
+impl MacOsVersion {
+    /// Creates a version from its components.
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+            rsr: None,
+            build: None,
+        }
+    }
+
+    /// Sets the Rapid Security Response letter.
+    pub fn with_rsr(mut self, rsr: char) -> Self {
+        self.rsr = Some(rsr);
+        self
+    }
+
+    /// Sets the build identifier.
+    pub fn with_build(mut self, build: impl Into<String>) -> Self {
+        self.build = Some(build.into());
+        self
+    }
+
+    /// Compares with `bound`, ignoring the build identifier unless `bound` has one.
+    fn cmp_bound(&self, bound: &MacOsVersion) -> Ordering {
+        let numbers = |v: &MacOsVersion| (v.major, v.minor, v.patch, v.rsr);
+        match numbers(self).cmp(&numbers(bound)) {
+            Ordering::Equal if bound.build.is_some() => self.build.cmp(&bound.build),
+            ordering => ordering,
+        }
+    }
+
+    /// Major version of the Darwin kernel shipping with this macOS version, e.g. `23` for 14.4.
+    ///
+    /// Returns `None` for versions which were never released, such as `10.16`, which is what
+    /// macOS 11 and later report to processes running with `SYSTEM_VERSION_COMPAT=1`.
+    pub fn darwin_major(&self) -> Option<u32> {
+        match self.major {
+            // Mac OS X 10.1 to macOS 10.15 are Darwin 5 to 19.
+            10 if (1..=15).contains(&self.minor) => Some(self.minor + 4),
+            // macOS 11 to 15 are Darwin 20 to 24.
+            11..=15 => Some(self.major + 9),
+            // macOS 26 continues at Darwin 25, versions 16 to 25 were skipped.
+            26.. => Some(self.major - 1),
+            _ => None,
+        }
+    }
+}
+
+impl Display for MacOsVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)?;
+        if self.patch != 0 {
+            write!(f, ".{}", self.patch)?;
+        }
+        if let Some(rsr) = self.rsr {
+            write!(f, " ({})", rsr)?;
+        }
+        if let Some(build) = &self.build {
+            write!(f, " ({})", build)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for MacOsVersion {
+    type Err = ParseVersionError;
+
+    /// Parses two- or three-component versions like `14.4` and `14.4.1`, optionally followed by
+    /// a parenthesized Rapid Security Response letter and build identifier like `(a) (22F770820d)`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseVersionError {
+            input: s.to_owned(),
+        };
+        let s = s.trim();
+        let (numbers, mut suffix) = s.split_at(s.find([' ', '(']).unwrap_or(s.len()));
+        let mut nums = numbers.split('.').map(|num| {
+            // `u32::from_str` accepts a leading `+`, which is not part of any version.
+            if num.bytes().all(|b| b.is_ascii_digit()) {
+                num.parse::<u32>().map_err(|_| err())
+            } else {
+                Err(err())
+            }
+        });
+        let major = nums.next().ok_or_else(err)??;
+        let minor = nums.next().ok_or_else(err)??;
+        let patch = nums.next().transpose()?.unwrap_or(0);
+        if nums.next().is_some() {
+            return Err(err());
+        }
+        let mut version = Self::new(major, minor, patch);
+
+        while !suffix.trim().is_empty() {
+            let (group, rest) = suffix
+                .trim_start()
+                .strip_prefix('(')
+                .and_then(|group| group.split_once(')'))
+                .ok_or_else(err)?;
+            let mut chars = group.chars();
+            match (chars.next(), chars.next()) {
+                // The letter comes before the build and only once.
+                (Some(rsr), None)
+                    if rsr.is_ascii_lowercase()
+                        && version.rsr.is_none()
+                        && version.build.is_none() =>
+                {
+                    version.rsr = Some(rsr)
+                }
+                // Builds look like `23E214`.
+                (Some(first), _)
+                    if first.is_ascii_digit()
+                        && group.chars().all(|c| c.is_ascii_alphanumeric())
+                        && version.build.is_none() =>
+                {
+                    version.build = Some(group.to_owned())
+                }
+                _ => return Err(err()),
+            }
+            suffix = rest;
+        }
+        Ok(version)
+    }
+}
+
+/// Error when parsing a [`MacOsVersion`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseVersionError {
+    pub(crate) input: String,
+}
+
+impl ParseVersionError {
+    /// The string that failed to parse.
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+}
+
+impl Display for ParseVersionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid macOS version `{}`", self.input)
+    }
+}
+
+impl std::error::Error for ParseVersionError {}
+
+/// Kind of a macOS build, classified from its build identifier (`kern.osversion`).
+///
+/// Build identifiers look like `23E224`: the Darwin major version, a letter for the update and a
+/// build number. Seeds carry a trailing lowercase letter, e.g. `23E5205c`, and have build numbers
+/// from 5000 on, while Rapid Security Responses like `22F770820d` have much longer ones.
+///
+/// # Example
+///
+/// ```
+/// use dikc_detector::BuildKind;
+///
+/// assert_eq!(BuildKind::classify("23E224"), Some(BuildKind::Release));
+/// assert_eq!(BuildKind::classify("23E5205c"), Some(BuildKind::Beta));
+/// assert_eq!(BuildKind::classify("Sonoma"), None);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum BuildKind {
+    /// A public release, including Rapid Security Responses.
+    Release,
+    /// A developer or public beta seed.
+    Beta,
+    /// Any other seed, e.g. an Apple-internal build.
+    Internal,
+}
+
+impl BuildKind {
+    /// Classifies the build identifier `build`, or returns `None` if it is malformed.
+    pub fn classify(build: &str) -> Option<Self> {
+        let darwin = build.find(|c: char| !c.is_ascii_digit())?;
+        let mut rest = build[darwin..].chars();
+        if darwin == 0 || !rest.next()?.is_ascii_uppercase() {
+            return None;
+        }
+        let rest = rest.as_str();
+        let (number, seed) = match rest.strip_suffix(|c: char| c.is_ascii_lowercase()) {
+            Some(number) => (number, true),
+            None => (rest, false),
+        };
+        if number.is_empty() || !number.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let number: u64 = number.parse().ok()?;
+        Some(match (seed, number) {
+            (false, _) | (true, 100_000..) => BuildKind::Release,
+            (true, 5000..=8999) => BuildKind::Beta,
+            (true, _) => BuildKind::Internal,
+        })
+    }
+}
+
+impl Display for BuildKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            BuildKind::Release => "release",
+            BuildKind::Beta => "beta",
+            BuildKind::Internal => "internal",
+        })
+    }
+}
+
+/// A range of [`MacOsVersion`]s.
+///
+/// Ranges are written as comparisons (`>=14.4`, `>14.4`, `<=14.3`, `<14.4`), Rust-style ranges
+/// (`15.0..15.2`, `15.0..=15.2`, `14.4..`, `..14.4`), pairs of comparisons (`>14.0, <=14.4`),
+/// exact pins (`14.4.1` or `=14.4.1`), or `*` for every version. Bounds may carry a Rapid Security Response letter and a build
+/// identifier, e.g. `>=13.4.1 (c)` or `=14.4 (23E214)`.
+///
+/// # Example
+///
+/// ```
+/// use dikc_detector::{MacOsVersion, VersionRange};
+///
+/// let range: VersionRange = "15.0..15.2".parse().unwrap();
+/// assert!(range.contains(&MacOsVersion::new(15, 1, 1)));
+/// assert!(!range.contains(&MacOsVersion::new(15, 2, 0)));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "String", into = "String")
+)]
+pub struct VersionRange {
+    start: Bound<MacOsVersion>,
+    end: Bound<MacOsVersion>,
+}
+
+impl VersionRange {
+    /// Creates a range from its bounds.
+    pub const fn new(start: Bound<MacOsVersion>, end: Bound<MacOsVersion>) -> Self {
+        Self { start, end }
+    }
+
+    /// Creates the range of versions equal to or newer than `version`.
+    pub const fn at_least(version: MacOsVersion) -> Self {
+        Self::new(Bound::Included(version), Bound::Unbounded)
+    }
+
+    /// The start bound of the range.
+    pub fn start(&self) -> Bound<&MacOsVersion> {
+        self.start.as_ref()
+    }
+
+    /// The end bound of the range.
+    pub fn end(&self) -> Bound<&MacOsVersion> {
+        self.end.as_ref()
+    }
+
+    /// Whether `version` is in the range.
+    ///
+    /// Build identifiers are only compared if the bound has one, so `=14.4` contains
+    /// `14.4 (23E214)` but `=14.4 (23E214)` doesn't contain `14.4 (23E5205c)`.
+    pub fn contains(&self, version: &MacOsVersion) -> bool {
+        let after_start = match &self.start {
+            Bound::Included(start) => version.cmp_bound(start).is_ge(),
+            Bound::Excluded(start) => version.cmp_bound(start).is_gt(),
+            Bound::Unbounded => true,
+        };
+        let before_end = match &self.end {
+            Bound::Included(end) => version.cmp_bound(end).is_le(),
+            Bound::Excluded(end) => version.cmp_bound(end).is_lt(),
+            Bound::Unbounded => true,
+        };
+        after_start && before_end
+    }
+}
+
+impl Display for VersionRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.start, &self.end) {
+            (Bound::Unbounded, Bound::Unbounded) => write!(f, "*"),
+            (Bound::Included(start), Bound::Unbounded) => write!(f, ">={}", start),
+            (Bound::Excluded(start), Bound::Unbounded) => write!(f, ">{}", start),
+            (Bound::Unbounded, Bound::Included(end)) => write!(f, "<={}", end),
+            (Bound::Unbounded, Bound::Excluded(end)) => write!(f, "<{}", end),
+            (Bound::Included(start), Bound::Included(end)) if start == end => {
+                write!(f, "={}", start)
+            }
+            (Bound::Included(start), Bound::Included(end)) => write!(f, "{}..={}", start, end),
+            (Bound::Included(start), Bound::Excluded(end)) => write!(f, "{}..{}", start, end),
+            (Bound::Excluded(start), Bound::Included(end)) => write!(f, ">{}, <={}", start, end),
+            (Bound::Excluded(start), Bound::Excluded(end)) => write!(f, ">{}, <{}", start, end),
+        }
+    }
+}
+
+impl FromStr for VersionRange {
+    type Err = ParseVersionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseVersionError {
+            input: s.to_owned(),
+        };
+        let version = |v: &str| v.trim().parse::<MacOsVersion>().map_err(|_| err());
+        let trimmed = s.trim();
+        let (start, end) = if let Some((lower, upper)) = trimmed.split_once(',') {
+            // Two comparisons, e.g. `>14.0, <=14.4`, as printed for excluded starts.
+            let lower: VersionRange = lower.parse().map_err(|_| err())?;
+            let upper: VersionRange = upper.parse().map_err(|_| err())?;
+            match (lower.start, lower.end, upper.start, upper.end) {
+                (start, Bound::Unbounded, Bound::Unbounded, end)
+                    if !matches!(start, Bound::Unbounded) && !matches!(end, Bound::Unbounded) =>
+                {
+                    (start, end)
+                }
+                _ => return Err(err()),
+            }
+        } else if trimmed == "*" {
+            (Bound::Unbounded, Bound::Unbounded)
+        } else if let Some(v) = trimmed.strip_prefix(">=") {
+            (Bound::Included(version(v)?), Bound::Unbounded)
+        } else if let Some(v) = trimmed.strip_prefix('>') {
+            (Bound::Excluded(version(v)?), Bound::Unbounded)
+        } else if let Some(v) = trimmed.strip_prefix("<=") {
+            (Bound::Unbounded, Bound::Included(version(v)?))
+        } else if let Some(v) = trimmed.strip_prefix('<') {
+            (Bound::Unbounded, Bound::Excluded(version(v)?))
+        } else if let Some((start, end)) = trimmed.split_once("..") {
+            let start = match start.trim() {
+                "" => Bound::Unbounded,
+                start => Bound::Included(version(start)?),
+            };
+            let end = match end.strip_prefix('=') {
+                Some(end) => Bound::Included(version(end)?),
+                None if end.trim().is_empty() => Bound::Unbounded,
+                None => Bound::Excluded(version(end)?),
+            };
+            (start, end)
+        } else {
+            let v = version(trimmed.strip_prefix('=').unwrap_or(trimmed))?;
+            (Bound::Included(v.clone()), Bound::Included(v))
+        };
+        Ok(Self::new(start, end))
+    }
+}
+
+impl TryFrom<String> for MacOsVersion {
+    type Error = ParseVersionError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<MacOsVersion> for String {
+    fn from(value: MacOsVersion) -> Self {
+        value.to_string()
+    }
+}
+
+impl TryFrom<String> for VersionRange {
+    type Error = ParseVersionError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<VersionRange> for String {
+    fn from(value: VersionRange) -> Self {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BuildKind, MacOsVersion, VersionRange};
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("14.4".parse(), Ok(MacOsVersion::new(14, 4, 0)));
+        assert_eq!("14.4.1".parse(), Ok(MacOsVersion::new(14, 4, 1)));
+        for bad in ["", "14", "14.", "14.4.1.1", "14.x", "+14.4", "fourteen"] {
+            let err = bad.parse::<MacOsVersion>().unwrap_err();
+            assert_eq!(err.input(), bad);
+        }
+    }
+
+    #[test]
+    fn test_order_and_display() {
+        let v = |s: &str| s.parse::<MacOsVersion>().unwrap();
+        assert!(v("14.3.1") < v("14.4"));
+        assert!(v("13.6.9") < v("14.0"));
+        assert_eq!(v("14.4.0"), v("14.4"));
+        assert_eq!(v("15.0.0").to_string(), "15.0");
+        assert_eq!(v("14.4.1").to_string(), "14.4.1");
+        assert!(v("13.4.1") < v("13.4.1 (a)"));
+        assert!(v("13.4.1 (a)") < v("13.4.1 (c)"));
+        assert!(v("13.4.1 (c)") < v("13.5"));
+    }
+
+    #[test]
+    fn test_parse_suffix() {
+        let v = |s: &str| s.parse::<MacOsVersion>();
+        assert_eq!(
+            v("13.4.1 (a)"),
+            Ok(MacOsVersion::new(13, 4, 1).with_rsr('a'))
+        );
+        assert_eq!(
+            v("13.4.1 (a) (22F770820d)"),
+            Ok(MacOsVersion::new(13, 4, 1)
+                .with_rsr('a')
+                .with_build("22F770820d"))
+        );
+        assert_eq!(
+            v("14.4(23E214)"),
+            Ok(MacOsVersion::new(14, 4, 0).with_build("23E214"))
+        );
+        assert_eq!(
+            v("13.4.1 (a) (22F770820d)").unwrap().to_string(),
+            "13.4.1 (a) (22F770820d)"
+        );
+        for bad in [
+            "13.4.1 a",
+            "13.4.1 (a",
+            "13.4.1 ()",
+            "13.4.1 (A1 B)",
+            "13.4.1 (22F770820d) (a)",
+            "13.4.1 (a) (b)",
+        ] {
+            assert!(v(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_build_kind() {
+        for (build, kind) in [
+            ("23E224", BuildKind::Release),
+            ("20G1427", BuildKind::Release),
+            ("22F770820d", BuildKind::Release),
+            ("23E5205c", BuildKind::Beta),
+            ("24A5264n", BuildKind::Beta),
+            ("24A123x", BuildKind::Internal),
+            ("24A9000a", BuildKind::Internal),
+        ] {
+            assert_eq!(BuildKind::classify(build), Some(kind), "{}", build);
+        }
+        for bad in [
+            "", "23", "E224", "23e224", "23E", "23Ec", "23E22x4", "23E224cc",
+        ] {
+            assert_eq!(BuildKind::classify(bad), None, "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_darwin_major() {
+        let v = |s: &str| s.parse::<MacOsVersion>().unwrap();
+        assert_eq!(v("10.15.7").darwin_major(), Some(19));
+        assert_eq!(v("10.16").darwin_major(), None);
+        assert_eq!(v("11.0.1").darwin_major(), Some(20));
+        assert_eq!(v("14.4").darwin_major(), Some(23));
+        assert_eq!(v("15.1").darwin_major(), Some(24));
+        assert_eq!(v("20.0").darwin_major(), None);
+        assert_eq!(v("26.0").darwin_major(), Some(25));
+    }
+
+    #[test]
+    fn test_range() {
+        let v = |s: &str| s.parse::<MacOsVersion>().unwrap();
+        let r = |s: &str| s.parse::<VersionRange>().unwrap();
+        assert!(r(">=14.4").contains(&v("14.4")));
+        assert!(!r(">=14.4").contains(&v("14.3.1")));
+        assert!(!r(">14.4").contains(&v("14.4")));
+        assert!(r("<=14.3").contains(&v("14.3")));
+        assert!(!r("<14.4").contains(&v("14.4")));
+        assert!(r("15.0..15.2").contains(&v("15.1.1")));
+        assert!(!r("15.0..15.2").contains(&v("15.2")));
+        assert!(r("15.0..=15.2").contains(&v("15.2")));
+        assert!(r("..14.4").contains(&v("10.15.7")));
+        assert!(r("14.4..").contains(&v("26.0")));
+        assert!(r("14.4.1").contains(&v("14.4.1")));
+        assert!(!r("=14.4.1").contains(&v("14.4.2")));
+        assert!(r("*").contains(&v("11.0")));
+        for s in [
+            ">=14.4",
+            "<14.4",
+            "15.0..15.2",
+            "15.0..=15.2",
+            "=14.4.1",
+            "*",
+        ] {
+            assert_eq!(r(s).to_string(), s);
+        }
+        assert!(r("=14.4").contains(&v("14.4 (23E214)")));
+        assert!(r("=14.4 (23E214)").contains(&v("14.4 (23E214)")));
+        assert!(!r("=14.4 (23E214)").contains(&v("14.4 (23E5205c)")));
+        assert!(r(">=13.4.1 (c)").contains(&v("13.4.1 (c) (22F770820d)")));
+        assert!(!r(">=13.4.1 (c)").contains(&v("13.4.1 (a)")));
+        assert!(r("<13.4.2").contains(&v("13.4.1 (c)")));
+        assert_eq!(r(">=13.4.1 (c)").to_string(), ">=13.4.1 (c)");
+        assert!(r(">14.0, <=14.4").contains(&v("14.4")));
+        assert!(!r(">14.0, <=14.4").contains(&v("14.0")));
+        for bad in [
+            "",
+            ">=",
+            "15..",
+            "14.4...15.0",
+            "14.x..15.0",
+            "<14.0, >15.0",
+            ">14.0, 15.0..",
+            ">14.0, <15.0, <16.0",
+        ] {
+            assert!(bad.parse::<VersionRange>().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_range_round_trip() {
+        use std::ops::Bound;
+
+        let bounds = |v: &str| {
+            let v: MacOsVersion = v.parse().unwrap();
+            [
+                Bound::Included(v.clone()),
+                Bound::Excluded(v),
+                Bound::Unbounded,
+            ]
+        };
+        for start in bounds("14.0") {
+            for end in bounds("15.1 (24B83)") {
+                let range = VersionRange::new(start.clone(), end);
+                let printed = range.to_string();
+                assert_eq!(printed.parse::<VersionRange>(), Ok(range), "{}", printed);
+            }
+        }
+    }
+}
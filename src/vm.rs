@@ -0,0 +1,192 @@
+//! Detection of virtual machines.
+
+use std::collections::BTreeMap;
+
+use crate::{Error, Rule, Severity, SystemInfo, VmMode, VmPolicy, KERN_HV_VMM_PRESENT};
+
+/// Prefixes of the `hw.model` identifiers of virtual Macs, e.g. `VirtualMac2,1` for
+/// Virtualization.framework and `VMware7,1` for VMware Fusion.
+const VIRTUAL_MODEL_PREFIXES: &[&str] = &["VirtualMac", "VMware"];
+
+/// Whether `system` is a virtual machine, either because the hypervisor says so
+/// (`kern.hv_vmm_present`) or because the `hw.model` is a known virtual one.
+///
+/// # Errors
+///
+/// Errors if neither value can be read.
+pub fn is_virtual(system: &dyn SystemInfo) -> Result<bool, Error> {
+    let vmm_present = match system.value_string(KERN_HV_VMM_PRESENT) {
+        Ok(value) => value.trim() == "1",
+        Err(Error::Missing(_)) => false,
+        Err(err) => return Err(err),
+    };
+    if vmm_present {
+        return Ok(true);
+    }
+    let model = system.hw_model()?;
+    Ok(VIRTUAL_MODEL_PREFIXES
+        .iter()
+        .any(|prefix| model.starts_with(prefix)))
+}
+
+/// Checks whether the Mac is a virtual machine as required by a [`VmPolicy`], and reports
+/// whether it is either way.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VmRule {
+    mode: VmMode,
+}
+
+impl VmRule {
+    /// Identifier of this rule.
+    pub const ID: &'static str = "vm";
+
+    /// Creates the rule enforcing the mode of `policy`.
+    pub fn new(policy: &VmPolicy) -> Self {
+        Self { mode: policy.mode }
+    }
+}
+
+impl Rule for VmRule {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+
+    fn description(&self) -> &str {
+        "Mac is physical or virtual as the policy requires"
+    }
+
+    fn evaluate(&self, system: &dyn SystemInfo) -> Result<(), Error> {
+        if self.mode == VmMode::Allow {
+            return Ok(());
+        }
+        match (self.mode, is_virtual(system)?) {
+            (VmMode::Forbid, true) => Err(Error::VirtualMachine {
+                model: system.hw_model().ok(),
+            }),
+            (VmMode::Require, false) => Err(Error::PhysicalMachine {
+                model: system.hw_model().ok(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    fn details(&self, system: &dyn SystemInfo) -> BTreeMap<String, String> {
+        is_virtual(system)
+            .map(|virtual_machine| ("vm.virtual".to_owned(), virtual_machine.to_string()))
+            .into_iter()
+            .collect()
+    }
+}
+
+/// Wrapper passing a rule on virtual machines, see [`VmPolicy::exempt_hardware`].
+///
+/// The wrapped rule keeps its ID, description and severity. Passes on virtual machines carry a
+/// `vm.exempt` detail.
+///
+/// # Example
+///
+/// ```
+/// use dikc_detector::{HardwarePolicy, MemoryRule, FakeSystem, Rule, VmExempt};
+///
+/// let mut policy = HardwarePolicy::default();
+/// policy.min_memory_gib = Some(16);
+/// let rule = VmExempt::new(MemoryRule::new(&policy));
+/// let system = FakeSystem::new()
+///     .with_hw_model("VirtualMac2,1")
+///     .with_value("hw.memsize", "4294967296");
+/// assert!(rule.evaluate(&system).is_ok());
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VmExempt<R> {
+    rule: R,
+}
+
+impl<R: Rule> VmExempt<R> {
+    /// Wraps `rule`.
+    pub fn new(rule: R) -> Self {
+        Self { rule }
+    }
+}
+
+impl<R: Rule> Rule for VmExempt<R> {
+    fn id(&self) -> &str {
+        self.rule.id()
+    }
+
+    fn description(&self) -> &str {
+        self.rule.description()
+    }
+
+    fn severity(&self) -> Severity {
+        self.rule.severity()
+    }
+
+    fn evaluate(&self, system: &dyn SystemInfo) -> Result<(), Error> {
+        if is_virtual(system).unwrap_or(false) {
+            Ok(())
+        } else {
+            self.rule.evaluate(system)
+        }
+    }
+
+    fn details(&self, system: &dyn SystemInfo) -> BTreeMap<String, String> {
+        if is_virtual(system).unwrap_or(false) {
+            BTreeMap::from([("vm.exempt".to_owned(), "true".to_owned())])
+        } else {
+            self.rule.details(system)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{is_virtual, VmExempt, VmRule};
+    use crate::{
+        Error, FakeSystem, HardwarePolicy, MemoryRule, Rule, VmMode, VmPolicy, HW_MEMSIZE,
+        KERN_HV_VMM_PRESENT,
+    };
+
+    #[test]
+    fn test_is_virtual() {
+        let physical = FakeSystem::new().with_hw_model("MacBookPro17,1");
+        assert!(!is_virtual(&physical).unwrap());
+        assert!(is_virtual(&physical.clone().with_value(KERN_HV_VMM_PRESENT, "1")).unwrap());
+        assert!(is_virtual(&FakeSystem::new().with_hw_model("VirtualMac2,1")).unwrap());
+        assert!(is_virtual(&FakeSystem::new().with_hw_model("VMware7,1")).unwrap());
+        assert!(is_virtual(&FakeSystem::new()).is_err());
+    }
+
+    #[test]
+    fn test_vm() {
+        let physical = FakeSystem::new().with_hw_model("Mac14,9");
+        let vm = FakeSystem::new().with_hw_model("VirtualMac2,1");
+        let rule = |mode| {
+            VmRule::new(&VmPolicy {
+                mode,
+                ..VmPolicy::default()
+            })
+        };
+        assert!(rule(VmMode::Allow).evaluate(&vm).is_ok());
+        assert_eq!(rule(VmMode::Allow).details(&vm)["vm.virtual"], "true");
+        assert!(matches!(
+            rule(VmMode::Forbid).evaluate(&vm),
+            Err(Error::VirtualMachine { .. })
+        ));
+        assert!(rule(VmMode::Forbid).evaluate(&physical).is_ok());
+        assert!(matches!(
+            rule(VmMode::Require).evaluate(&physical),
+            Err(Error::PhysicalMachine { .. })
+        ));
+
+        let memory = VmExempt::new(MemoryRule::new(&HardwarePolicy {
+            min_memory_gib: Some(16),
+            ..HardwarePolicy::default()
+        }));
+        let small_vm = vm.with_value(HW_MEMSIZE, "4294967296");
+        assert!(memory.evaluate(&small_vm).is_ok());
+        assert_eq!(memory.details(&small_vm)["vm.exempt"], "true");
+        assert!(memory
+            .evaluate(&physical.with_value(HW_MEMSIZE, "4294967296"))
+            .is_err());
+    }
+}